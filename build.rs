@@ -0,0 +1,124 @@
+extern crate phf_codegen;
+
+use std::collections::HashSet;
+use std::env;
+use std::fs::File;
+use std::io::{BufWriter,Read,Write};
+use std::path::{Path,PathBuf};
+use std::process::Command;
+
+/* build.rs
+ *
+ * Description: Generates the IEEE Registration Authority vendor maps used by
+ * src/layer2/ethernet/oui.rs from the CSV listings in data/ieee. The files are read in the
+ * format IEEE publishes them in: "Registry,Assignment,Organization Name,Organization Address",
+ * with quoted fields where the name or address contains a comma.
+ *
+ * Setting NETDEFS_IEEE_FETCH=1 downloads current listings with curl into OUT_DIR and uses
+ * those instead of the checked-in copies.
+ *
+ */
+
+// Map name, listing, assignment width in hex digits and where IEEE publishes the listing.
+const REGISTRIES: [(&str, &str, usize, &str); 3] = [
+    ("MA_L", "data/ieee/oui.csv", 6, "https://standards-oui.ieee.org/oui/oui.csv"),
+    ("MA_M", "data/ieee/mam.csv", 7, "https://standards-oui.ieee.org/oui28/mam.csv"),
+    ("MA_S", "data/ieee/oui36.csv", 9, "https://standards-oui.ieee.org/oui36/oui36.csv"),
+];
+
+fn fetch(url: &str, out_dir: &Path) -> PathBuf {
+    let target = out_dir.join(url.rsplit('/').next().unwrap());
+    let status = Command::new("curl").args(["-fsSL", "-o"]).arg(&target).arg(url).status()
+        .unwrap_or_else(|e| panic!("cannot run curl for {}: {}", url, e));
+    if !status.success() {
+        panic!("downloading {} failed: {}", url, status);
+    }
+    target
+}
+
+// Splits one CSV record into fields, undoing quoting.
+fn split_record(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            },
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+fn read_registry(path: &Path, digits: usize) -> Vec<(u64, String)> {
+    let path = path.display();
+    let mut text = String::new();
+    File::open(path.to_string()).and_then(|mut x| x.read_to_string(&mut text)).unwrap_or_else(|e| panic!("cannot read {}: {}", path, e));
+
+    let mut seen = HashSet::new();
+    let mut entries = Vec::new();
+    for (number, line) in text.lines().enumerate().skip(1) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = split_record(line);
+        if fields.len() < 3 || fields[1].len() != digits {
+            panic!("{}:{}: expected a {} digit assignment", path, number + 1, digits);
+        }
+        let key = u64::from_str_radix(&fields[1], 16).unwrap_or_else(|_| panic!("{}:{}: bad assignment {}", path, number + 1, fields[1]));
+        // A handful of early MA-L assignments are listed more than once. Keep the first.
+        if seen.insert(key) {
+            entries.push((key, fields[2].trim().to_string()));
+        }
+    }
+    entries
+}
+
+// phf_codegen::Map is typed by its key, and its PhfHash bound lives in a crate we don't depend
+// on directly, so each key width gets its own expansion.
+macro_rules! write_map {
+    ($file: expr, $name: expr, $key_type: ty, $entries: expr) => {{
+        let values = $entries.iter().map(|x| format!("{:?}", x.1)).collect::<Vec<String>>();
+        let mut map = phf_codegen::Map::<$key_type>::new();
+        map.phf_path("::_internal__phf");
+        for (entry, value) in $entries.iter().zip(values.iter()) {
+            map.entry(entry.0 as $key_type, value);
+        }
+        write!($file, "static {}: ::_internal__phf::Map<{}, &'static str> = ", $name, stringify!($key_type)).unwrap();
+        map.build(&mut $file).unwrap();
+        writeln!($file, ";").unwrap();
+    }};
+}
+
+fn main() {
+    for &(_, path, _, _) in REGISTRIES.iter() {
+        println!("cargo:rerun-if-changed={}", path);
+    }
+    println!("cargo:rerun-if-env-changed=NETDEFS_IEEE_FETCH");
+    if env::var_os("CARGO_FEATURE_OUI_REGISTRY").is_none() {
+        return;
+    }
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let download = env::var("NETDEFS_IEEE_FETCH").map(|x| x == "1").unwrap_or(false);
+    let [large, medium, small] = REGISTRIES.map(|(name, path, digits, url)| {
+        let entries = read_registry(&if download { fetch(url, &out_dir) } else { PathBuf::from(path) }, digits);
+        if entries.is_empty() {
+            println!("cargo:warning={} has no assignments, so {} lookups always miss. Run data/ieee/update.sh.", path, name);
+        }
+        entries
+    });
+
+    let out = out_dir.join("oui_registry.rs");
+    let mut file = BufWriter::new(File::create(&out).unwrap());
+    write_map!(file, REGISTRIES[0].0, u32, large);
+    write_map!(file, REGISTRIES[1].0, u32, medium);
+    write_map!(file, REGISTRIES[2].0, u64, small);
+}
@@ -0,0 +1,8 @@
+#!/bin/sh
+# Refreshes the IEEE Registration Authority listings that build.rs turns into the vendor maps
+# behind the "oui-registry" feature. Run from anywhere; needs curl.
+set -e
+cd "$(dirname "$0")"
+curl -fsSL -o oui.csv https://standards-oui.ieee.org/oui/oui.csv
+curl -fsSL -o mam.csv https://standards-oui.ieee.org/oui28/mam.csv
+curl -fsSL -o oui36.csv https://standards-oui.ieee.org/oui36/oui36.csv
@@ -38,8 +38,8 @@ define_proc_macros! {
 
         let mut map = phf_codegen::Map::new();
         map.phf_path("::netdefs::_internal__phf");
-        for &(ref key, ref value) in &pairs {
-            map.entry(*key, &**value);
+        for (key, value) in &pairs {
+            map.entry(*key, value);
         }
 
         let mut tokens = quote! {
@@ -65,8 +65,8 @@ define_proc_macros! {
 
         let mut map = phf_codegen::Map::new();
         map.phf_path("::netdefs::_internal__phf");
-        for &(ref key, ref value) in &pairs {
-            map.entry(*key, &**value);
+        for (key, value) in &pairs {
+            map.entry(*key, value);
         }
 
         let mut tokens = quote! {
@@ -193,14 +193,20 @@ pub fn _extract_input(derive_input: &str) -> &str {
 
     for expected in &["#[allow(unused)]", "enum", "ProceduralMasqueradeDummyType", "{",
                      "Input", "=", "(0,", "stringify!", "("] {
-        input = input.trim_left();
+        input = input.trim_start();
         assert!(input.starts_with(expected),
                 "expected prefix {:?} not found in {:?}", expected, derive_input);
         input = &input[expected.len()..];
     }
 
-    for expected in [")", ").0,", "}"].iter().rev() {
-        input = input.trim_right();
+    // Newer compilers drop the trailing comma after the last variant when
+    // stringifying the derive input, so it is optional here.
+    for expected in [")", ").0", "}"].iter().rev() {
+        input = input.trim_end();
+        if *expected == ").0" && input.ends_with(",") {
+            input = &input[..input.len() - 1];
+            input = input.trim_end();
+        }
         assert!(input.ends_with(expected),
                 "expected suffix {:?} not found in {:?}", expected, derive_input);
         let end = input.len() - expected.len();
@@ -0,0 +1,389 @@
+use std::error::Error;
+use std::fmt;
+
+use layer3::NetworkProtocol;
+use super::ethernet::{FrameView,MacAddress};
+
+const AOE_HEADER_LEN: usize = 10;
+const ATA_ARGS_LEN: usize = 12;
+const CONFIG_ARGS_LEN: usize = 8;
+
+const FLAG_RESPONSE: u8 = 0x08;
+const FLAG_ERROR: u8 = 0x04;
+
+// Major 0xFFFF and minor 0xFF address every target.
+pub const AOE_BROADCAST_MAJOR: u16 = 0xFFFF;
+pub const AOE_BROADCAST_MINOR: u8 = 0xFF;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AoeError {
+    Truncated { needed: usize, actual: usize },
+    NotAoe(u16),
+}
+
+impl Error for AoeError {
+    fn description(&self) -> &str {
+        match *self {
+            AoeError::Truncated { .. } => "AoE packet is truncated.",
+            AoeError::NotAoe(_) => "Frame does not carry AoE.",
+        }
+    }
+}
+
+impl fmt::Display for AoeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AoeError::Truncated { needed, actual } => write!(f,"AoE packet is truncated. Needed {} bytes, got {}", needed, actual),
+            AoeError::NotAoe(val) => write!(f,"Frame does not carry AoE. EtherType: {:04X}", val),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AoeCommand {
+    IssueAta,
+    QueryConfig,
+    MacMaskList,
+    ReserveRelease,
+    Other(u8),
+}
+
+impl AoeCommand {
+    pub fn from_u8(val: u8) -> AoeCommand {
+        match val {
+            0 => AoeCommand::IssueAta,
+            1 => AoeCommand::QueryConfig,
+            2 => AoeCommand::MacMaskList,
+            3 => AoeCommand::ReserveRelease,
+            _ => AoeCommand::Other(val),
+        }
+    }
+}
+
+impl fmt::Display for AoeCommand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self,f)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AoeHeader {
+    version: u8,
+    flags: u8,
+    error: u8,
+    major: u16,
+    minor: u8,
+    command: AoeCommand,
+    tag: u32,
+}
+
+impl AoeHeader {
+    pub fn parse(bytes: &[u8]) -> Result<AoeHeader, AoeError> {
+        if bytes.len() < AOE_HEADER_LEN {
+            return Err(AoeError::Truncated { needed: AOE_HEADER_LEN, actual: bytes.len() });
+        }
+        Ok(AoeHeader {
+            version: bytes[0] >> 4,
+            flags: bytes[0] & 0x0F,
+            error: bytes[1],
+            major: ((bytes[2] as u16) << 8) + (bytes[3] as u16),
+            minor: bytes[4],
+            command: AoeCommand::from_u8(bytes[5]),
+            tag: ((bytes[6] as u32) << 24) + ((bytes[7] as u32) << 16) + ((bytes[8] as u32) << 8) + (bytes[9] as u32),
+        })
+    }
+
+    pub fn get_version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn get_flags(&self) -> u8 {
+        self.flags
+    }
+
+    pub fn is_response(&self) -> bool {
+        self.flags & FLAG_RESPONSE != 0
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.flags & FLAG_ERROR != 0
+    }
+
+    // Only meaningful when is_error() is set.
+    pub fn get_error(&self) -> u8 {
+        self.error
+    }
+
+    /// Shelf address.
+    pub fn get_major(&self) -> u16 {
+        self.major
+    }
+
+    /// Slot address.
+    pub fn get_minor(&self) -> u8 {
+        self.minor
+    }
+
+    pub fn is_broadcast_target(&self) -> bool {
+        self.major == AOE_BROADCAST_MAJOR || self.minor == AOE_BROADCAST_MINOR
+    }
+
+    pub fn get_command(&self) -> AoeCommand {
+        self.command
+    }
+
+    // Set by the initiator and echoed back, so it pairs responses with requests.
+    pub fn get_tag(&self) -> u32 {
+        self.tag
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AtaArgs {
+    aflags: u8,
+    err_feature: u8,
+    sector_count: u8,
+    cmd_status: u8,
+    lba: u64,
+}
+
+impl AtaArgs {
+    pub const AFLAG_EXTENDED: u8 = 0x40;
+    pub const AFLAG_DEVICE: u8 = 0x10;
+    pub const AFLAG_ASYNC: u8 = 0x02;
+    pub const AFLAG_WRITE: u8 = 0x01;
+
+    fn parse(bytes: &[u8]) -> AtaArgs {
+        let lba = bytes[4..10].iter().rev().fold(0u64, |acc, &x| (acc << 8) | x as u64);
+        AtaArgs { aflags: bytes[0], err_feature: bytes[1], sector_count: bytes[2], cmd_status: bytes[3], lba }
+    }
+
+    pub fn get_aflags(&self) -> u8 {
+        self.aflags
+    }
+
+    pub fn is_write(&self) -> bool {
+        self.aflags & AtaArgs::AFLAG_WRITE != 0
+    }
+
+    pub fn is_lba48(&self) -> bool {
+        self.aflags & AtaArgs::AFLAG_EXTENDED != 0
+    }
+
+    // Feature in requests, error in responses.
+    pub fn get_err_feature(&self) -> u8 {
+        self.err_feature
+    }
+
+    pub fn get_sector_count(&self) -> u8 {
+        self.sector_count
+    }
+
+    // ATA command in requests, status in responses.
+    pub fn get_cmd_status(&self) -> u8 {
+        self.cmd_status
+    }
+
+    /// The 48 bit LBA. LBA bytes go out least significant first.
+    pub fn get_lba(&self) -> u64 {
+        self.lba
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigArgs {
+    buffer_count: u16,
+    firmware_version: u16,
+    sector_count: u8,
+    aoe_version: u8,
+    ccmd: u8,
+    config_string: Vec<u8>,
+}
+
+impl ConfigArgs {
+    fn parse(bytes: &[u8]) -> Result<ConfigArgs, AoeError> {
+        let string_len = ((bytes[6] as usize) << 8) + (bytes[7] as usize);
+        if bytes.len() < CONFIG_ARGS_LEN + string_len {
+            return Err(AoeError::Truncated { needed: AOE_HEADER_LEN + CONFIG_ARGS_LEN + string_len, actual: AOE_HEADER_LEN + bytes.len() });
+        }
+        Ok(ConfigArgs {
+            buffer_count: ((bytes[0] as u16) << 8) + (bytes[1] as u16),
+            firmware_version: ((bytes[2] as u16) << 8) + (bytes[3] as u16),
+            sector_count: bytes[4],
+            aoe_version: bytes[5] >> 4,
+            ccmd: bytes[5] & 0x0F,
+            config_string: bytes[CONFIG_ARGS_LEN..CONFIG_ARGS_LEN + string_len].to_vec(),
+        })
+    }
+
+    // Outstanding requests the target can queue.
+    pub fn get_buffer_count(&self) -> u16 {
+        self.buffer_count
+    }
+
+    pub fn get_firmware_version(&self) -> u16 {
+        self.firmware_version
+    }
+
+    // Maximum sectors per ATA command, zero meaning 2.
+    pub fn get_sector_count(&self) -> u8 {
+        self.sector_count
+    }
+
+    pub fn get_aoe_version(&self) -> u8 {
+        self.aoe_version
+    }
+
+    pub fn get_ccmd(&self) -> u8 {
+        self.ccmd
+    }
+
+    pub fn get_config_string(&self) -> &[u8] {
+        &self.config_string
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AoeArgs {
+    Ata(AtaArgs),
+    Config(ConfigArgs),
+    // Commands whose arguments are not decoded.
+    Raw(Vec<u8>),
+}
+
+/// An AoE packet together with the MAC addresses of the frame that carried it.
+#[derive(Clone, Debug)]
+pub struct AoeView<'a> {
+    src_mac: MacAddress,
+    dst_mac: MacAddress,
+    header: AoeHeader,
+    args: AoeArgs,
+    data: &'a [u8],
+}
+
+impl<'a> AoeView<'a> {
+    pub fn from_frame(frame: &FrameView<'a>) -> Result<AoeView<'a>, AoeError> {
+        match frame.get_ethertype() {
+            Ok(NetworkProtocol::ATAoE) => (),
+            _ => return Err(AoeError::NotAoe(frame.get_ethertype_raw())),
+        }
+        let bytes = frame.get_network_payload();
+        let header = AoeHeader::parse(bytes)?;
+        let rest = &bytes[AOE_HEADER_LEN..];
+        let (args, data) = match header.command {
+            AoeCommand::IssueAta => {
+                if rest.len() < ATA_ARGS_LEN {
+                    return Err(AoeError::Truncated { needed: AOE_HEADER_LEN + ATA_ARGS_LEN, actual: bytes.len() });
+                }
+                (AoeArgs::Ata(AtaArgs::parse(rest)), &rest[ATA_ARGS_LEN..])
+            },
+            AoeCommand::QueryConfig => {
+                if rest.len() < CONFIG_ARGS_LEN {
+                    return Err(AoeError::Truncated { needed: AOE_HEADER_LEN + CONFIG_ARGS_LEN, actual: bytes.len() });
+                }
+                let config = ConfigArgs::parse(rest)?;
+                let end = CONFIG_ARGS_LEN + config.config_string.len();
+                (AoeArgs::Config(config), &rest[end..])
+            },
+            _ => (AoeArgs::Raw(rest.to_vec()), &rest[rest.len()..]),
+        };
+        Ok(AoeView { src_mac: frame.get_src_mac(), dst_mac: frame.get_dst_mac(), header, args, data })
+    }
+
+    pub fn get_header(&self) -> AoeHeader {
+        self.header
+    }
+
+    pub fn get_args(&self) -> &AoeArgs {
+        &self.args
+    }
+
+    /// Sector data for ATA commands. May include frame padding.
+    pub fn get_data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub fn get_src_mac(&self) -> MacAddress {
+        self.src_mac
+    }
+
+    pub fn get_dst_mac(&self) -> MacAddress {
+        self.dst_mac
+    }
+
+    /// The storage target's MAC: the source of a response, or the destination of a request
+    /// (which may be broadcast).
+    pub fn get_target_mac(&self) -> MacAddress {
+        if self.header.is_response() { self.src_mac } else { self.dst_mac }
+    }
+
+    pub fn get_initiator_mac(&self) -> MacAddress {
+        if self.header.is_response() { self.dst_mac } else { self.src_mac }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::ethernet::{FrameBuilder,LlcHeader,SnapHeader,SAP_SNAP,OUI_RFC1042};
+
+    #[test]
+    fn parses_ata_response() {
+        let mut payload = vec![0x18, 0x00, 0x00, 0x07, 0x02, 0x00, 0xDE, 0xAD, 0xBE, 0xEF];
+        payload.extend_from_slice(&[0x41, 0x00, 0x01, 0x50, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x00, 0x00]);
+        payload.extend(vec![0xAB; 512]);
+        let target = MacAddress::from_bytes([0x00, 0x10, 0x04, 0, 0, 1]);
+        let host = MacAddress::from_bytes([0x02, 0, 0, 0, 0, 2]);
+        let bytes = FrameBuilder::new(host, target, NetworkProtocol::ATAoE).payload(&payload).build();
+        let frame = FrameView::new(&bytes).unwrap();
+
+        let aoe = AoeView::from_frame(&frame).unwrap();
+        let header = aoe.get_header();
+        assert_eq!(header.get_version(), 1);
+        assert!(header.is_response() && !header.is_error());
+        assert_eq!((header.get_major(), header.get_minor()), (7, 2));
+        assert_eq!(header.get_tag(), 0xDEADBEEF);
+        assert_eq!(aoe.get_target_mac(), target);
+        assert_eq!(aoe.get_initiator_mac(), host);
+        match *aoe.get_args() {
+            AoeArgs::Ata(ata) => {
+                assert!(ata.is_write() && ata.is_lba48());
+                assert_eq!(ata.get_lba(), 0x060504030201);
+                assert_eq!(ata.get_cmd_status(), 0x50);
+            },
+            _ => panic!("expected ATA arguments"),
+        }
+        assert_eq!(aoe.get_data().len(), 512);
+
+        let mut snap = LlcHeader::new(SAP_SNAP, SAP_SNAP, 0x03).to_bytes();
+        snap.extend_from_slice(&SnapHeader::new(OUI_RFC1042, 0x88A2).to_bytes());
+        snap.extend_from_slice(&payload);
+        let bytes = FrameBuilder::ieee8023(host, target).payload(&snap).build();
+        let aoe = AoeView::from_frame(&FrameView::new(&bytes).unwrap()).unwrap();
+        assert_eq!(aoe.get_header().get_tag(), 0xDEADBEEF);
+        assert_eq!(aoe.get_data().len(), 512);
+    }
+
+    #[test]
+    fn parses_config_query() {
+        let mut payload = vec![0x10, 0x00, 0xFF, 0xFF, 0xFF, 0x01, 0, 0, 0, 1];
+        payload.extend_from_slice(&[0x00, 0x10, 0x40, 0x0B, 0x02, 0x10, 0x00, 0x03]);
+        payload.extend_from_slice(b"abc");
+        let mac = MacAddress::from_bytes([0x02, 0, 0, 0, 0, 2]);
+        let bytes = FrameBuilder::new(MacAddress::from_bytes([0xFF;6]), mac, NetworkProtocol::ATAoE).payload(&payload).build();
+        let aoe = AoeView::from_frame(&FrameView::new(&bytes).unwrap()).unwrap();
+        assert!(aoe.get_header().is_broadcast_target());
+        match *aoe.get_args() {
+            AoeArgs::Config(ref config) => {
+                assert_eq!(config.get_buffer_count(), 16);
+                assert_eq!(config.get_aoe_version(), 1);
+                assert_eq!(config.get_config_string(), b"abc");
+            },
+            _ => panic!("expected config arguments"),
+        }
+
+        payload[17] = 0x30;
+        let bytes = FrameBuilder::new(MacAddress::from_bytes([0xFF;6]), mac, NetworkProtocol::ATAoE).payload(&payload).build();
+        assert!(AoeView::from_frame(&FrameView::new(&bytes).unwrap()).is_err());
+    }
+}
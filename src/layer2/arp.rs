@@ -0,0 +1,290 @@
+use std::error::Error;
+use std::fmt;
+use std::net::Ipv4Addr;
+
+use layer3::NetworkProtocol;
+use super::ethernet::{BindingEvent,FrameBuilder,FrameView,IpMacCombo,IpMacSet,MacAddress};
+
+const ARP_PACKET_LEN: usize = 28;
+const HTYPE_ETHERNET: u16 = 1;
+const PTYPE_IPV4: u16 = 0x0800;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArpError {
+    Truncated { needed: usize, actual: usize },
+    // Only Ethernet hardware addresses and IPv4 protocol addresses are supported.
+    UnsupportedAddressType { htype: u16, ptype: u16, hlen: u8, plen: u8 },
+    UnknownOperation(u16),
+    // The frame is not carrying ARP or RARP. Holds its EtherType.
+    NotArp(u16),
+}
+
+impl Error for ArpError {
+    fn description(&self) -> &str {
+        match *self {
+            ArpError::Truncated { .. } => "ARP packet is truncated.",
+            ArpError::UnsupportedAddressType { .. } => "ARP packet is not for Ethernet and IPv4.",
+            ArpError::UnknownOperation(_) => "ARP operation is not recognized.",
+            ArpError::NotArp(_) => "Frame does not carry an ARP packet.",
+        }
+    }
+}
+
+impl fmt::Display for ArpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ArpError::Truncated { needed, actual } => write!(f,"ARP packet is truncated. Needed {} bytes, got {}", needed, actual),
+            ArpError::UnsupportedAddressType { htype, ptype, hlen, plen } => write!(f,"ARP packet is not for Ethernet and IPv4. HTYPE: {}, PTYPE: {:04X}, HLEN: {}, PLEN: {}", htype, ptype, hlen, plen),
+            ArpError::UnknownOperation(val) => write!(f,"ARP operation was not recognized. Operation: {}", val),
+            ArpError::NotArp(val) => write!(f,"Frame does not carry an ARP packet. EtherType: {:04X}", val),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArpOperation {
+    Request,
+    Reply,
+    ReverseRequest,
+    ReverseReply,
+}
+
+impl ArpOperation {
+    pub fn from_u16(val: u16) -> Result<ArpOperation, ArpError> {
+        match val {
+            1 => Ok(ArpOperation::Request),
+            2 => Ok(ArpOperation::Reply),
+            3 => Ok(ArpOperation::ReverseRequest),
+            4 => Ok(ArpOperation::ReverseReply),
+            _ => Err(ArpError::UnknownOperation(val)),
+        }
+    }
+
+    pub fn to_u16(&self) -> u16 {
+        match *self {
+            ArpOperation::Request => 1,
+            ArpOperation::Reply => 2,
+            ArpOperation::ReverseRequest => 3,
+            ArpOperation::ReverseReply => 4,
+        }
+    }
+
+    pub fn is_reverse(&self) -> bool {
+        *self == ArpOperation::ReverseRequest || *self == ArpOperation::ReverseReply
+    }
+}
+
+impl fmt::Display for ArpOperation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self,f)
+    }
+}
+
+/// An ARP or RARP packet for Ethernet and IPv4, the only combination seen in practice.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ArpPacket {
+    operation: ArpOperation,
+    sender_mac: MacAddress,
+    sender_ip: Ipv4Addr,
+    target_mac: MacAddress,
+    target_ip: Ipv4Addr,
+}
+
+impl ArpPacket {
+    pub fn new(operation: ArpOperation, sender_mac: MacAddress, sender_ip: Ipv4Addr, target_mac: MacAddress, target_ip: Ipv4Addr) -> ArpPacket {
+        ArpPacket { operation, sender_mac, sender_ip, target_mac, target_ip }
+    }
+
+    /// Who has `target_ip`? The target MAC is left zeroed.
+    pub fn request(sender_mac: MacAddress, sender_ip: Ipv4Addr, target_ip: Ipv4Addr) -> ArpPacket {
+        ArpPacket::new(ArpOperation::Request, sender_mac, sender_ip, MacAddress::from_bytes([0;6]), target_ip)
+    }
+
+    pub fn reply(sender_mac: MacAddress, sender_ip: Ipv4Addr, target_mac: MacAddress, target_ip: Ipv4Addr) -> ArpPacket {
+        ArpPacket::new(ArpOperation::Reply, sender_mac, sender_ip, target_mac, target_ip)
+    }
+
+    /// An unsolicited announcement of `ip` at `mac`, sent as a request with the sender and
+    /// target IP both set to `ip`.
+    pub fn gratuitous(mac: MacAddress, ip: Ipv4Addr) -> ArpPacket {
+        ArpPacket::request(mac, ip, ip)
+    }
+
+    /// Parses the ARP payload of a frame. Trailing padding is ignored.
+    pub fn parse(bytes: &[u8]) -> Result<ArpPacket, ArpError> {
+        if bytes.len() < ARP_PACKET_LEN {
+            return Err(ArpError::Truncated { needed: ARP_PACKET_LEN, actual: bytes.len() });
+        }
+        let htype = ((bytes[0] as u16) << 8) + (bytes[1] as u16);
+        let ptype = ((bytes[2] as u16) << 8) + (bytes[3] as u16);
+        let (hlen, plen) = (bytes[4], bytes[5]);
+        if htype != HTYPE_ETHERNET || ptype != PTYPE_IPV4 || hlen != 6 || plen != 4 {
+            return Err(ArpError::UnsupportedAddressType { htype, ptype, hlen, plen });
+        }
+        let operation = ArpOperation::from_u16(((bytes[6] as u16) << 8) + (bytes[7] as u16))?;
+
+        Ok(ArpPacket {
+            operation,
+            sender_mac: MacAddress::from_slice(&bytes[8..14]),
+            sender_ip: Ipv4Addr::new(bytes[14], bytes[15], bytes[16], bytes[17]),
+            target_mac: MacAddress::from_slice(&bytes[18..24]),
+            target_ip: Ipv4Addr::new(bytes[24], bytes[25], bytes[26], bytes[27]),
+        })
+    }
+
+    pub fn from_frame(frame: &FrameView) -> Result<ArpPacket, ArpError> {
+        match frame.get_ethertype() {
+            Ok(NetworkProtocol::ARP) | Ok(NetworkProtocol::Reverse_ARP) => ArpPacket::parse(frame.get_network_payload()),
+            _ => Err(ArpError::NotArp(frame.get_ethertype_raw())),
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8;28] {
+        let mut bytes = [0u8;28];
+        bytes[0..2].copy_from_slice(&HTYPE_ETHERNET.to_be_bytes());
+        bytes[2..4].copy_from_slice(&PTYPE_IPV4.to_be_bytes());
+        bytes[4] = 6;
+        bytes[5] = 4;
+        bytes[6..8].copy_from_slice(&self.operation.to_u16().to_be_bytes());
+        bytes[8..14].copy_from_slice(&self.sender_mac.to_bytes());
+        bytes[14..18].copy_from_slice(&self.sender_ip.octets());
+        bytes[18..24].copy_from_slice(&self.target_mac.to_bytes());
+        bytes[24..28].copy_from_slice(&self.target_ip.octets());
+        bytes
+    }
+
+    /// Wraps the packet in an Ethernet frame from the sender MAC. Requests are broadcast and
+    /// replies go to the target MAC.
+    pub fn to_frame(&self) -> FrameBuilder {
+        let dst_mac = match self.operation {
+            ArpOperation::Request | ArpOperation::ReverseRequest => MacAddress::broadcast(),
+            ArpOperation::Reply | ArpOperation::ReverseReply => self.target_mac,
+        };
+        let proto = if self.operation.is_reverse() { NetworkProtocol::Reverse_ARP } else { NetworkProtocol::ARP };
+        FrameBuilder::new(dst_mac, self.sender_mac, proto).payload(&self.to_bytes())
+    }
+
+    pub fn get_operation(&self) -> ArpOperation {
+        self.operation
+    }
+
+    pub fn get_sender_mac(&self) -> MacAddress {
+        self.sender_mac
+    }
+
+    pub fn get_sender_ip(&self) -> Ipv4Addr {
+        self.sender_ip
+    }
+
+    pub fn get_target_mac(&self) -> MacAddress {
+        self.target_mac
+    }
+
+    pub fn get_target_ip(&self) -> Ipv4Addr {
+        self.target_ip
+    }
+
+    pub fn is_gratuitous(&self) -> bool {
+        !self.operation.is_reverse() && self.sender_ip == self.target_ip
+    }
+
+    // An address probe (RFC 5227) has no sender IP yet.
+    pub fn is_probe(&self) -> bool {
+        self.operation == ArpOperation::Request && self.sender_ip.is_unspecified()
+    }
+
+    /// The IP to MAC bindings this packet vouches for: the sender of any ARP packet, plus the
+    /// target of a reply. Probes and zeroed addresses are skipped.
+    pub fn get_bindings(&self) -> Vec<IpMacCombo> {
+        let mut bindings = Vec::new();
+        if self.operation.is_reverse() {
+            return bindings;
+        }
+        if !self.sender_ip.is_unspecified() {
+            bindings.push(IpMacCombo::new(self.sender_ip, self.sender_mac));
+        }
+        if self.operation == ArpOperation::Reply && !self.target_ip.is_unspecified() && !self.target_mac.is_nil()
+            && self.target_ip != self.sender_ip {
+            bindings.push(IpMacCombo::new(self.target_ip, self.target_mac));
+        }
+        bindings
+    }
+
+    /// Records this packet's bindings in `set`. Returns the ones that were not already there.
+    pub fn learn(&self, set: &mut IpMacSet) -> Vec<IpMacCombo> {
+        self.get_bindings().into_iter().filter(|x| set.learn(x.get_ip().unwrap(), x.get_mac().unwrap())).collect()
+    }
+
+    /// Like `learn`, but returns what `IpMacSet::observe` reported for each binding. Feed every
+    /// ARP packet on a segment through this to catch poisoning as it happens.
+    pub fn observe(&self, set: &mut IpMacSet) -> Vec<BindingEvent> {
+        self.get_bindings().into_iter().flat_map(|x| set.observe(x.get_ip().unwrap(), x.get_mac().unwrap())).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::ethernet::{LlcHeader,SnapHeader,SAP_SNAP,OUI_RFC1042};
+
+    #[test]
+    fn round_trips_through_a_frame() {
+        let mac = MacAddress::from_bytes([0x02, 0, 0, 0, 0, 0x01]);
+        let request = ArpPacket::request(mac, Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2));
+        let bytes = request.to_frame().build();
+        let frame = FrameView::new(&bytes).unwrap();
+        assert_eq!(frame.get_dst_mac(), MacAddress::broadcast());
+        assert_eq!(ArpPacket::from_frame(&frame).unwrap(), request);
+    }
+
+    #[test]
+    fn round_trips_through_a_snap_frame() {
+        let mac = MacAddress::from_bytes([0x02, 0, 0, 0, 0, 0x01]);
+        let reply = ArpPacket::reply(mac, Ipv4Addr::new(10, 0, 0, 1), MacAddress::from_bytes([0x02, 0, 0, 0, 0, 0x02]), Ipv4Addr::new(10, 0, 0, 2));
+        let mut payload = LlcHeader::new(SAP_SNAP, SAP_SNAP, 0x03).to_bytes();
+        payload.extend_from_slice(&SnapHeader::new(OUI_RFC1042, 0x0806).to_bytes());
+        payload.extend_from_slice(&reply.to_bytes());
+        let bytes = FrameBuilder::ieee8023(reply.get_target_mac(), mac).payload(&payload).build();
+
+        let frame = FrameView::new(&bytes).unwrap();
+        assert!(frame.is_8023());
+        assert_eq!(frame.get_network_payload().len(), 28);
+        assert_eq!(ArpPacket::from_frame(&frame).unwrap(), reply);
+    }
+
+    #[test]
+    fn rejects_bad_packets() {
+        let mut bytes = ArpPacket::gratuitous(MacAddress::from_bytes([1;6]), Ipv4Addr::new(10, 0, 0, 1)).to_bytes();
+        assert_eq!(ArpPacket::parse(&bytes[..27]).unwrap_err(), ArpError::Truncated { needed: 28, actual: 27 });
+        bytes[7] = 9;
+        assert_eq!(ArpPacket::parse(&bytes).unwrap_err(), ArpError::UnknownOperation(9));
+        bytes[1] = 6;
+        assert!(ArpPacket::parse(&bytes).is_err());
+    }
+
+    #[test]
+    fn learns_bindings_into_set() {
+        let a = MacAddress::from_bytes([0x02, 0, 0, 0, 0, 0x0A]);
+        let b = MacAddress::from_bytes([0x02, 0, 0, 0, 0, 0x0B]);
+        let a_ip = Ipv4Addr::new(192, 168, 1, 10);
+        let b_ip = Ipv4Addr::new(192, 168, 1, 11);
+        let mut set = IpMacSet::new();
+        set.push_ip(a_ip);
+
+        let reply = ArpPacket::reply(b, b_ip, a, a_ip);
+        assert_eq!(reply.learn(&mut set).len(), 2);
+        assert_eq!(reply.learn(&mut set).len(), 0);
+        assert!(set.contains(&IpMacCombo::new(a_ip, a)));
+        assert!(set.contains(&IpMacCombo::new(b_ip, b)));
+        assert!(!set.contains(&IpMacCombo::from_ip(a_ip)));
+
+        let probe = ArpPacket::request(a, Ipv4Addr::new(0, 0, 0, 0), b_ip);
+        assert!(probe.is_probe());
+        assert!(probe.learn(&mut set).is_empty());
+
+        let spoofed = ArpPacket::gratuitous(MacAddress::from_bytes([0x02, 0, 0, 0, 0, 0x66]), b_ip);
+        let events = spoofed.observe(&mut set);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].get_old(), Some(IpMacCombo::new(b_ip, b)));
+    }
+}
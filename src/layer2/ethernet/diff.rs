@@ -0,0 +1,294 @@
+use std::collections::{HashMap,VecDeque};
+use std::fmt;
+use std::hash::Hash;
+
+use super::ipmac::{EntryInfo,IpMacCombo,IpMacSet};
+use super::snapshot::Snapshot;
+
+/// A binding that exists on both sides of a diff under a different partner: either the IP now
+/// answers from another MAC, or the MAC now holds another IP of the same family.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BindingChange {
+    old: IpMacCombo,
+    new: IpMacCombo,
+}
+
+impl BindingChange {
+    pub fn new(old: IpMacCombo, new: IpMacCombo) -> BindingChange {
+        BindingChange { old, new }
+    }
+
+    pub fn get_old(&self) -> IpMacCombo {
+        self.old
+    }
+
+    pub fn get_new(&self) -> IpMacCombo {
+        self.new
+    }
+
+    pub fn is_mac_change(&self) -> bool {
+        self.old.get_ip() == self.new.get_ip()
+    }
+
+    pub fn is_ip_change(&self) -> bool {
+        self.old.get_mac() == self.new.get_mac()
+    }
+}
+
+impl fmt::Display for BindingChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"{} -> {}", self.old, self.new)
+    }
+}
+
+/// What it takes to turn one IpMacSet into another, from `IpMacSet::diff`. Only complete
+/// bindings are paired up as changes; an IP-only entry that gained a MAC shows up as removed
+/// and added. Each list is sorted by IP and then MAC, and duplicate entries count once.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IpMacDiff {
+    added: Vec<IpMacCombo>,
+    removed: Vec<IpMacCombo>,
+    changed: Vec<BindingChange>,
+}
+
+impl IpMacDiff {
+    pub fn get_added(&self) -> &[IpMacCombo] {
+        &self.added
+    }
+
+    pub fn get_removed(&self) -> &[IpMacCombo] {
+        &self.removed
+    }
+
+    pub fn get_changed(&self) -> &[BindingChange] {
+        &self.changed
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+// One line per difference, prefixed like a unified diff: "+", "-" or "~".
+impl fmt::Display for IpMacDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for entry in &self.added {
+            writeln!(f,"+ {}", entry)?;
+        }
+        for entry in &self.removed {
+            writeln!(f,"- {}", entry)?;
+        }
+        for change in &self.changed {
+            writeln!(f,"~ {}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/// How `IpMacSet::merge` settles an incoming entry that conflicts with existing ones. Two
+/// entries conflict when they share an IP, or share a MAC while one of them has no IP. Complete
+/// bindings that only share a MAC never conflict: one interface may hold several addresses.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MergePolicy {
+    PreferNewer,    // Whichever side was seen last wins, even a partial entry over a complete one
+    PreferComplete, // Complete bindings beat partial entries; otherwise the newer side wins
+    KeepBoth,       // Conflicting complete bindings are all kept; partial entries yield to them
+}
+
+impl fmt::Display for MergePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self,f)
+    }
+}
+
+fn conflicts(a: &IpMacCombo, b: &IpMacCombo) -> bool {
+    if a == b {
+        return false;
+    }
+    let same_ip = a.has_ip() && a.get_ip() == b.get_ip();
+    let same_mac = a.has_mac() && a.get_mac() == b.get_mac() && !(a.has_ip() && b.has_ip());
+    same_ip || same_mac
+}
+
+fn is_complete(entry: &IpMacCombo) -> bool {
+    entry.has_ip() && entry.has_mac()
+}
+
+// The distinct entries in `from` that are not in `other`, in a stable order.
+fn only_in(from: &IpMacSet, other: &IpMacSet) -> Vec<IpMacCombo> {
+    let mut only = from.iter().filter(|x| !other.contains(x)).cloned().collect::<Vec<IpMacCombo>>();
+    only.sort_by_key(|x| (x.get_ip(), x.get_mac()));
+    only.dedup();
+    only
+}
+
+// Pairs each entry in `added` with the first unpaired entry in `removed` that has the same
+// key, and takes both out of their lists. Entries without a key are never paired.
+fn pair_off<K: Hash + Eq, F: Fn(&IpMacCombo) -> Option<K>>(removed: &mut Vec<IpMacCombo>, added: &mut Vec<IpMacCombo>, key: F) -> Vec<BindingChange> {
+    let mut waiting: HashMap<K, VecDeque<usize>> = HashMap::new();
+    for (pos, entry) in removed.iter().enumerate() {
+        if let Some(k) = key(entry) {
+            waiting.entry(k).or_default().push_back(pos);
+        }
+    }
+
+    let mut paired = vec![false; removed.len()];
+    let mut changed = Vec::new();
+    added.retain(|entry| {
+        match key(entry).and_then(|k| waiting.get_mut(&k)).and_then(|x| x.pop_front()) {
+            Some(pos) => {
+                paired[pos] = true;
+                changed.push(BindingChange::new(removed[pos], *entry));
+                false
+            },
+            None => true,
+        }
+    });
+    let mut pos = 0;
+    removed.retain(|_| {
+        pos += 1;
+        !paired[pos - 1]
+    });
+    changed
+}
+
+impl IpMacSet {
+    /// Compares this set with a `newer` one. IPs whose MAC changed are paired up first, then
+    /// MACs whose IP changed within the same address family.
+    pub fn diff(&self, newer: &IpMacSet) -> IpMacDiff {
+        let mut removed = only_in(self, newer);
+        let mut added = only_in(newer, self);
+
+        let mut changed = pair_off(&mut removed, &mut added, |x| {
+            if is_complete(x) { x.get_ip() } else { None }
+        });
+        changed.extend(pair_off(&mut removed, &mut added, |x| {
+            if is_complete(x) { x.get_mac().map(|mac| (mac, x.is_ipv4())) } else { None }
+        }));
+        changed.sort_by_key(|x| (x.get_new().get_ip(), x.get_new().get_mac()));
+
+        IpMacDiff { added, removed, changed }
+    }
+
+    /// Folds `other` into this set. An entry present on both sides stays where it is, keeping
+    /// the earliest first-seen and the latest last-seen time, and the TTL and state of whichever
+    /// side saw it last. Conflicts are settled by `policy`. Returns the entries dropped from
+    /// either side.
+    pub fn merge(&mut self, other: &IpMacSet, policy: MergePolicy) -> Vec<IpMacCombo> {
+        let mut dropped = Vec::new();
+        for (&entry, &info) in other.iter_with_info() {
+            if self.contains(&entry) {
+                let pos = self.get_index(entry);
+                let current = *self.get_info_by_index(pos).unwrap();
+                let newer = if info.get_last_seen() > current.get_last_seen() { info } else { current };
+                let first_seen = current.get_first_seen().min(info.get_first_seen());
+                self.set_info_by_index(pos, EntryInfo::from_parts(first_seen, newer.get_last_seen(), newer.get_ttl(), newer.get_state()));
+                continue;
+            }
+
+            let mut rivals = entry.get_ip().map_or(Vec::new(), |x| self.get_indices_by_ip(x));
+            rivals.extend(entry.get_mac().map_or(Vec::new(), |x| self.get_indices_by_mac(x)));
+            rivals.retain(|x| conflicts(&entry, self.get(*x).unwrap()));
+            rivals.sort();
+            rivals.dedup();
+
+            let rank = |combo: &IpMacCombo, info: &EntryInfo| match policy {
+                MergePolicy::PreferComplete => (is_complete(combo), info.get_last_seen()),
+                _ => (false, info.get_last_seen()),
+            };
+            if policy == MergePolicy::KeepBoth {
+                // A partial entry only ever conflicts with complete bindings, and yields to them.
+                if !is_complete(&entry) && !rivals.is_empty() {
+                    dropped.push(entry);
+                    continue;
+                }
+                rivals.retain(|x| !is_complete(self.get(*x).unwrap()));
+            }
+            else if rivals.iter().any(|x| rank(self.get(*x).unwrap(), self.get_info_by_index(*x).unwrap()) >= rank(&entry, &info)) {
+                dropped.push(entry);
+                continue;
+            }
+
+            dropped.extend(self.swap_remove_indices(rivals));
+            self.push_with_info(entry, info);
+        }
+        dropped
+    }
+}
+
+impl Snapshot {
+    pub fn diff(&self, newer: &Snapshot) -> IpMacDiff {
+        self.get_set().diff(newer.get_set())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::test_util::mac;
+    use chrono::{Duration,Utc};
+    use std::net::Ipv4Addr;
+
+    fn ip(last: u8) -> Ipv4Addr {
+        Ipv4Addr::new(10, 0, 0, last)
+    }
+
+    #[test]
+    fn diffs_sets() {
+        let mut old = IpMacSet::new();
+        old.learn(ip(1), mac(1));
+        old.learn(ip(2), mac(2));
+        old.learn(ip(3), mac(3));
+        old.push_ip(ip(4));
+
+        let mut new = IpMacSet::new();
+        new.learn(ip(1), mac(1));
+        new.learn(ip(2), mac(66));
+        new.learn(ip(33), mac(3));
+        new.learn(ip(4), mac(4));
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.get_added(), &[IpMacCombo::new(ip(4), mac(4))]);
+        assert_eq!(diff.get_removed(), &[IpMacCombo::from_ip(ip(4))]);
+        assert_eq!(diff.get_changed(), &[
+            BindingChange::new(IpMacCombo::new(ip(2), mac(2)), IpMacCombo::new(ip(2), mac(66))),
+            BindingChange::new(IpMacCombo::new(ip(3), mac(3)), IpMacCombo::new(ip(33), mac(3))),
+        ]);
+        assert!(diff.get_changed()[0].is_mac_change() && diff.get_changed()[1].is_ip_change());
+        assert!(diff.to_string().contains("~ 10.0.0.2 is-at 02:00:00:00:00:02 -> 10.0.0.2 is-at 02:00:00:00:00:42\n"));
+        assert!(new.diff(&new.clone()).is_empty());
+    }
+
+    #[test]
+    fn merges_with_policies() {
+        let start = Utc::now();
+        let later = start + Duration::seconds(10);
+        let mut ours = IpMacSet::new();
+        ours.learn_at(ip(1), mac(1), later);
+        ours.learn_at(ip(2), mac(2), start);
+        ours.push_at(IpMacCombo::from_ip(ip(3)), later);
+
+        let mut theirs = IpMacSet::new();
+        theirs.learn_at(ip(1), mac(1), start);
+        theirs.learn_at(ip(2), mac(66), later);
+        theirs.learn_at(ip(3), mac(3), start);
+
+        let mut newer = ours.clone();
+        let dropped = newer.merge(&theirs, MergePolicy::PreferNewer);
+        assert_eq!(dropped, vec![IpMacCombo::new(ip(2), mac(2)), IpMacCombo::new(ip(3), mac(3))]);
+        assert!(newer.contains(&IpMacCombo::new(ip(2), mac(66))) && newer.contains(&IpMacCombo::from_ip(ip(3))));
+        let info = newer.get_info(&IpMacCombo::new(ip(1), mac(1))).unwrap();
+        assert_eq!((info.get_first_seen(), info.get_last_seen()), (start, later));
+        assert_eq!(newer.get(0), Some(&IpMacCombo::new(ip(1), mac(1))));
+
+        let mut complete = ours.clone();
+        complete.merge(&theirs, MergePolicy::PreferComplete);
+        assert!(complete.contains(&IpMacCombo::new(ip(3), mac(3))) && !complete.contains_mac(mac(2)));
+
+        let mut both = ours.clone();
+        assert_eq!(both.merge(&theirs, MergePolicy::KeepBoth), vec![IpMacCombo::from_ip(ip(3))]);
+        assert!(both.contains(&IpMacCombo::new(ip(2), mac(2))) && both.contains(&IpMacCombo::new(ip(2), mac(66))));
+        assert!(both.ip_has_multiple_macs(ip(2)));
+        assert!(!both.contains(&IpMacCombo::from_ip(ip(3))));
+    }
+}
@@ -0,0 +1,185 @@
+use std::fmt;
+use std::net::IpAddr;
+
+use chrono::{DateTime,Utc};
+
+use super::ipmac::{IpMacCombo,IpMacSet};
+use super::mac::MacAddress;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BindingEventKind {
+    NewBinding,             // First binding seen for this IP
+    IpMoved,                // IP now answers from a MAC it has never used before
+    FlipFlop,               // IP went back to a MAC it used earlier, e.g. dueling gratuitous ARPs
+    MacClaimsManyIps(usize),// MAC now holds this many IPv4 addresses, more than the threshold
+}
+
+impl fmt::Display for BindingEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self,f)
+    }
+}
+
+/// Something `IpMacSet::observe` noticed. `old` is the binding that was current before: the
+/// IP's previous MAC for IpMoved and FlipFlop, or the MAC's most recent other IP for
+/// MacClaimsManyIps. It is None for NewBinding.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BindingEvent {
+    kind: BindingEventKind,
+    old: Option<IpMacCombo>,
+    new: IpMacCombo,
+    time: DateTime<Utc>,
+}
+
+impl BindingEvent {
+    pub fn new(kind: BindingEventKind, old: Option<IpMacCombo>, new: IpMacCombo, time: DateTime<Utc>) -> BindingEvent {
+        BindingEvent { kind, old, new, time }
+    }
+
+    pub fn get_kind(&self) -> BindingEventKind {
+        self.kind
+    }
+
+    pub fn get_old(&self) -> Option<IpMacCombo> {
+        self.old
+    }
+
+    pub fn get_new(&self) -> IpMacCombo {
+        self.new
+    }
+
+    pub fn get_time(&self) -> DateTime<Utc> {
+        self.time
+    }
+
+    // Everything but a plain new binding is worth a look from a spoofing point of view.
+    pub fn is_conflict(&self) -> bool {
+        self.kind != BindingEventKind::NewBinding
+    }
+}
+
+impl fmt::Display for BindingEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let show = |x: Option<IpMacCombo>| match x {
+            Some(combo) => combo.to_string(),
+            None => "-".to_string(),
+        };
+        write!(f,"{} {}: {} -> {}", self.time.to_rfc3339(), self.kind, show(self.old), show(Some(self.new)))
+    }
+}
+
+impl IpMacSet {
+    // The complete binding for `ip` seen most recently. Ties go to the later position, so a
+    // burst of replies within one clock tick always resolves the same way. Without removals in
+    // between, that is the binding learned last.
+    fn latest_binding_for_ip(&self, ip: IpAddr) -> Option<IpMacCombo> {
+        self.get_indices_by_ip(ip).into_iter()
+            .filter(|x| self.get(*x).unwrap().has_mac())
+            .max_by_key(|x| (self.get_info_by_index(*x).unwrap().get_last_seen(), *x))
+            .map(|x| *self.get(x).unwrap())
+    }
+
+    // The IPv4 binding for `mac` seen most recently, other than the one for `except`. Ties are
+    // broken as in `latest_binding_for_ip`.
+    fn latest_binding_for_mac(&self, mac: MacAddress, except: IpAddr) -> Option<IpMacCombo> {
+        self.get_indices_by_mac(mac).into_iter()
+            .filter(|x| self.get(*x).unwrap().get_ip().is_some_and(|ip| ip.is_ipv4() && ip != except))
+            .max_by_key(|x| (self.get_info_by_index(*x).unwrap().get_last_seen(), *x))
+            .map(|x| *self.get(x).unwrap())
+    }
+
+    /// Learns a binding like `learn` and reports what changed. A refresh of the binding the IP
+    /// already uses reports nothing.
+    pub fn observe<I: Into<IpAddr>>(&mut self, ip: I, mac: MacAddress) -> Vec<BindingEvent> {
+        self.observe_at(ip, mac, Utc::now())
+    }
+
+    pub fn observe_at<I: Into<IpAddr>>(&mut self, ip: I, mac: MacAddress, now: DateTime<Utc>) -> Vec<BindingEvent> {
+        let ip = ip.into();
+        let new = IpMacCombo::new(ip, mac);
+        let previous = self.latest_binding_for_ip(ip);
+        let learned = self.learn_at(ip, mac, now);
+
+        let mut events = Vec::new();
+        match previous {
+            None => events.push(BindingEvent::new(BindingEventKind::NewBinding, None, new, now)),
+            Some(old) if old.get_mac() != Some(mac) => {
+                let kind = if learned { BindingEventKind::IpMoved } else { BindingEventKind::FlipFlop };
+                events.push(BindingEvent::new(kind, Some(old), new, now));
+            },
+            Some(_) => (),
+        }
+
+        if learned && ip.is_ipv4() {
+            let mut distinct = self.get_ips_by_mac(mac).into_iter().filter(|x| x.is_ipv4()).collect::<Vec<IpAddr>>();
+            distinct.sort();
+            distinct.dedup();
+            if distinct.len() > self.get_claim_threshold() {
+                let old = self.latest_binding_for_mac(mac, ip);
+                events.push(BindingEvent::new(BindingEventKind::MacClaimsManyIps(distinct.len()), old, new, now));
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::test_util::mac;
+    use chrono::Duration;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn reports_moves_and_flip_flops() {
+        let start = Utc::now();
+        let gateway = Ipv4Addr::new(10, 0, 0, 1);
+        let mut set = IpMacSet::new();
+
+        let events = set.observe_at(gateway, mac(1), start);
+        assert_eq!(events, vec![BindingEvent::new(BindingEventKind::NewBinding, None, IpMacCombo::new(gateway, mac(1)), start)]);
+        assert!(set.observe_at(gateway, mac(1), start + Duration::seconds(1)).is_empty());
+
+        let events = set.observe_at(gateway, mac(66), start + Duration::seconds(2));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].get_kind(), BindingEventKind::IpMoved);
+        assert_eq!(events[0].get_old(), Some(IpMacCombo::new(gateway, mac(1))));
+        assert!(events[0].is_conflict());
+
+        let events = set.observe_at(gateway, mac(1), start + Duration::seconds(3));
+        assert_eq!(events[0].get_kind(), BindingEventKind::FlipFlop);
+        assert_eq!(events[0].get_old(), Some(IpMacCombo::new(gateway, mac(66))));
+        assert_eq!(events[0].get_time(), start + Duration::seconds(3));
+
+        // Both earlier bindings share a timestamp; the one learned last is the current one.
+        let later = start + Duration::seconds(4);
+        let router = Ipv4Addr::new(10, 0, 0, 254);
+        set.observe_at(router, mac(1), later);
+        set.observe_at(router, mac(2), later);
+        let events = set.observe_at(router, mac(3), later);
+        assert_eq!(events[0].get_old(), Some(IpMacCombo::new(router, mac(2))));
+    }
+
+    #[test]
+    fn reports_macs_claiming_many_ips() {
+        let start = Utc::now();
+        let mut set = IpMacSet::new();
+        set.set_claim_threshold(2);
+        assert!(set.observe_at(Ipv4Addr::new(10, 0, 0, 1), mac(66), start).iter().all(|x| !x.is_conflict()));
+        assert!(set.observe_at(mac(66).to_ipv6_link_local(), mac(66), start).iter().all(|x| !x.is_conflict()));
+        // Exactly at the threshold is still allowed.
+        assert!(set.observe_at(Ipv4Addr::new(10, 0, 0, 2), mac(66), start).iter().all(|x| !x.is_conflict()));
+
+        let events = set.observe_at(Ipv4Addr::new(10, 0, 0, 3), mac(66), start);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].get_kind(), BindingEventKind::MacClaimsManyIps(3));
+        assert_eq!(events[1].get_old(), Some(IpMacCombo::new(Ipv4Addr::new(10, 0, 0, 2), mac(66))));
+        assert!(events[1].to_string().contains("MacClaimsManyIps(3)"));
+
+        let mut default = IpMacSet::new();
+        for i in 1..=4u8 {
+            assert!(default.observe_at(Ipv4Addr::new(10, 0, 1, i), mac(7), start).iter().all(|x| !x.is_conflict()));
+        }
+        assert_eq!(default.observe_at(Ipv4Addr::new(10, 0, 1, 5), mac(7), start)[1].get_kind(), BindingEventKind::MacClaimsManyIps(5));
+    }
+}
@@ -0,0 +1,138 @@
+// IEEE 802.3 CRC-32, reflected form of the 0x04C11DB7 polynomial.
+const CRC32_POLY: u32 = 0xEDB88320;
+
+// Slice-by-8 lookup tables. TABLES[0] is the classic byte-at-a-time table, and
+// TABLES[n][i] is the CRC of byte i followed by n zero bytes, which lets the main
+// loop fold eight input bytes per step.
+static TABLES: [[u32;256];8] = build_tables();
+
+const fn build_tables() -> [[u32;256];8] {
+    let mut tables = [[0u32;256];8];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ CRC32_POLY } else { crc >> 1 };
+            bit += 1;
+        }
+        tables[0][i] = crc;
+        i += 1;
+    }
+    let mut n = 1;
+    while n < 8 {
+        let mut i = 0;
+        while i < 256 {
+            let prev = tables[n - 1][i];
+            tables[n][i] = (prev >> 8) ^ tables[0][(prev & 0xFF) as usize];
+            i += 1;
+        }
+        n += 1;
+    }
+    tables
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFFFFFFu32;
+    let mut chunks = bytes.chunks_exact(8);
+    for chunk in &mut chunks {
+        let lo = crc ^ u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        let hi = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+        crc = TABLES[7][(lo & 0xFF) as usize]
+            ^ TABLES[6][((lo >> 8) & 0xFF) as usize]
+            ^ TABLES[5][((lo >> 16) & 0xFF) as usize]
+            ^ TABLES[4][(lo >> 24) as usize]
+            ^ TABLES[3][(hi & 0xFF) as usize]
+            ^ TABLES[2][((hi >> 8) & 0xFF) as usize]
+            ^ TABLES[1][((hi >> 16) & 0xFF) as usize]
+            ^ TABLES[0][(hi >> 24) as usize];
+    }
+    for byte in chunks.remainder() {
+        crc = (crc >> 8) ^ TABLES[0][((crc ^ *byte as u32) & 0xFF) as usize];
+    }
+    !crc
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FCS {
+    bytes: u32,
+}
+
+impl FCS {
+    // The FCS goes out least significant byte first, so this reads it little endian
+    // to get the CRC-32 value as it is usually written.
+    pub fn from_slice(byte_slice: &[u8]) -> FCS {
+        assert!(byte_slice.len() == 4);
+        FCS { bytes: u32::from_le_bytes([byte_slice[0], byte_slice[1], byte_slice[2], byte_slice[3]]) }
+    }
+
+    /// Computes the FCS over everything from the destination MAC to the end of the payload.
+    pub fn compute(frame_bytes: &[u8]) -> FCS {
+        FCS { bytes: crc32(frame_bytes) }
+    }
+
+    /// Checks a frame whose last four bytes are its FCS. Anything shorter than an FCS fails.
+    pub fn verify(frame_bytes: &[u8]) -> bool {
+        if frame_bytes.len() < 4 {
+            return false;
+        }
+        let (data, fcs) = frame_bytes.split_at(frame_bytes.len() - 4);
+        FCS::compute(data) == FCS::from_slice(fcs)
+    }
+
+    /// Checks this FCS against a frame that does not include it.
+    pub fn matches(&self, frame_bytes: &[u8]) -> bool {
+        *self == FCS::compute(frame_bytes)
+    }
+
+    pub fn get_value(&self) -> u32 {
+        self.bytes
+    }
+
+    /// The FCS in transmission order, ready to be appended to a frame.
+    pub fn to_bytes(&self) -> [u8;4] {
+        self.bytes.to_le_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn crc32_bitwise(bytes: &[u8]) -> u32 {
+        let mut crc = 0xFFFFFFFFu32;
+        for byte in bytes {
+            crc ^= *byte as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 == 1 { (crc >> 1) ^ CRC32_POLY } else { crc >> 1 };
+            }
+        }
+        !crc
+    }
+
+    #[test]
+    fn check_value() {
+        assert_eq!(FCS::compute(b"123456789").get_value(), 0xCBF43926);
+        assert_eq!(FCS::compute(&[]).get_value(), 0);
+    }
+
+    #[test]
+    fn slice_by_8_matches_bitwise() {
+        let data = (0..1000u32).map(|x| (x.wrapping_mul(2654435761) >> 24) as u8).collect::<Vec<u8>>();
+        for len in 0..data.len() {
+            assert_eq!(crc32(&data[..len]), crc32_bitwise(&data[..len]));
+        }
+    }
+
+    #[test]
+    fn verify_detects_corruption() {
+        let mut frame = vec![0x5A; 60];
+        let fcs = FCS::compute(&frame);
+        assert!(fcs.matches(&frame));
+        frame.extend_from_slice(&fcs.to_bytes());
+        assert!(FCS::verify(&frame));
+        frame[20] ^= 0x01;
+        assert!(!FCS::verify(&frame));
+        assert!(!FCS::verify(&[0, 0, 0]));
+    }
+}
@@ -0,0 +1,447 @@
+use std::error::Error;
+use std::fmt;
+use std::cmp;
+
+use layer3::{NetworkProtocol,NetworkProtocolNotRecognizedError};
+use super::{MacAddress,FCS,parse_ethertype,to_ethertype};
+use super::llc::LlcPdu;
+use super::vlan::{VLAN_Tag,VlanStack,VLAN_TAG_LEN,is_vlan_tpid};
+
+// Offsets into an Ethernet II header. Everything on the wire is big endian.
+const DST_MAC_OFFSET: usize = 0;
+const SRC_MAC_OFFSET: usize = 6;
+const TYPE_OFFSET: usize = 12;
+const UNTAGGED_HEADER_LEN: usize = 14;
+const FCS_LEN: usize = 4;
+const MIN_FRAME_LEN: usize = 60;    // Excluding the FCS.
+
+// Type/length values up to this are an 802.3 length, from 0x0600 up an EtherType.
+const MAX_8023_LENGTH: u16 = 1500;
+const MIN_ETHERTYPE: u16 = 0x0600;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FrameError {
+    // The buffer ended before the header, a VLAN tag or the FCS could be read.
+    Truncated { needed: usize, actual: usize },
+    // The type/length field is between 1501 and 1535, which is neither.
+    InvalidTypeLength(u16),
+    // An 802.3 length field claims more payload than the frame holds.
+    LengthMismatch { length: usize, available: usize },
+}
+
+impl Error for FrameError {
+    fn description(&self) -> &str {
+        match *self {
+            FrameError::Truncated { .. } => "Ethernet frame is truncated.",
+            FrameError::InvalidTypeLength(_) => "Ethernet type/length field is neither a length nor an EtherType.",
+            FrameError::LengthMismatch { .. } => "802.3 length field is larger than the frame payload.",
+        }
+    }
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FrameError::Truncated { needed, actual } => write!(f,"Ethernet frame is truncated. Needed at least {} bytes, got {}", needed, actual),
+            FrameError::InvalidTypeLength(val) => write!(f,"Ethernet type/length field is neither a length nor an EtherType: {:04X}", val),
+            FrameError::LengthMismatch { length, available } => write!(f,"802.3 length field is larger than the frame payload. Length: {}, payload: {}", length, available),
+        }
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    ((bytes[offset] as u16) << 8) + (bytes[offset + 1] as u16)
+}
+
+/// A borrowed, zero-copy view over a single Ethernet II or IEEE 802.3 frame.
+///
+/// Nothing is copied out of the underlying buffer until one of the getters is called.
+/// Frames are not required to meet the 60 byte minimum, since captures taken on the
+/// sending host usually see them before padding is added.
+#[derive(Clone, Copy, Debug)]
+pub struct FrameView<'a> {
+    bytes: &'a [u8],
+    tag_count: usize,
+    payload_end: usize,
+    has_fcs: bool,
+}
+
+impl<'a> FrameView<'a> {
+    /// Parses a frame without a trailing FCS, as delivered by most capture interfaces.
+    pub fn new(bytes: &'a [u8]) -> Result<FrameView<'a>, FrameError> {
+        FrameView::parse(bytes, false)
+    }
+
+    /// Parses a frame whose last four bytes are the FCS.
+    pub fn with_fcs(bytes: &'a [u8]) -> Result<FrameView<'a>, FrameError> {
+        FrameView::parse(bytes, true)
+    }
+
+    fn parse(bytes: &'a [u8], has_fcs: bool) -> Result<FrameView<'a>, FrameError> {
+        let trailer = if has_fcs { FCS_LEN } else { 0 };
+        let mut needed = UNTAGGED_HEADER_LEN + trailer;
+        if bytes.len() < needed {
+            return Err(FrameError::Truncated { needed, actual: bytes.len() });
+        }
+
+        let mut tag_count = 0;
+        let mut offset = TYPE_OFFSET;
+        while is_vlan_tpid(read_u16(bytes, offset)) {
+            needed += VLAN_TAG_LEN;
+            if bytes.len() < needed {
+                return Err(FrameError::Truncated { needed, actual: bytes.len() });
+            }
+            tag_count += 1;
+            offset += VLAN_TAG_LEN;
+        }
+
+        let type_length = read_u16(bytes, offset);
+        let header_len = offset + 2;
+        let mut payload_end = bytes.len() - trailer;
+        if type_length <= MAX_8023_LENGTH {
+            let available = payload_end - header_len;
+            if type_length as usize > available {
+                return Err(FrameError::LengthMismatch { length: type_length as usize, available });
+            }
+            // Anything past the length is padding.
+            payload_end = header_len + type_length as usize;
+        }
+        else if type_length < MIN_ETHERTYPE {
+            return Err(FrameError::InvalidTypeLength(type_length));
+        }
+
+        Ok(FrameView { bytes, tag_count, payload_end, has_fcs })
+    }
+
+    pub fn get_dst_mac(&self) -> MacAddress {
+        MacAddress::from_slice(&self.bytes[DST_MAC_OFFSET..DST_MAC_OFFSET + 6])
+    }
+
+    pub fn get_src_mac(&self) -> MacAddress {
+        MacAddress::from_slice(&self.bytes[SRC_MAC_OFFSET..SRC_MAC_OFFSET + 6])
+    }
+
+    /// The VLAN tags in the order they appear on the wire, outermost first.
+    pub fn get_vlan_stack(&self) -> VlanStack {
+        VlanStack::parse(&self.bytes[TYPE_OFFSET..TYPE_OFFSET + self.tag_count * VLAN_TAG_LEN]).0
+    }
+
+    pub fn is_vlan_tagged(&self) -> bool {
+        self.tag_count > 0
+    }
+
+    /// The raw type/length field following any VLAN tags. For 802.3 frames this is the length.
+    pub fn get_ethertype_raw(&self) -> u16 {
+        read_u16(self.bytes, self.header_len() - 2)
+    }
+
+    /// The protocol carried by the frame. For 802.3 frames this comes from the SNAP PID, and
+    /// frames without SNAP return an error holding the length field.
+    pub fn get_ethertype(&self) -> Result<NetworkProtocol, NetworkProtocolNotRecognizedError> {
+        if self.is_8023() {
+            match self.get_llc() {
+                Ok(Some(llc)) => match llc.get_snap() {
+                    Some(snap) => snap.get_protocol(),
+                    None => Err(NetworkProtocolNotRecognizedError(self.get_ethertype_raw())),
+                },
+                _ => Err(NetworkProtocolNotRecognizedError(self.get_ethertype_raw())),
+            }
+        }
+        else {
+            parse_ethertype(self.get_ethertype_raw())
+        }
+    }
+
+    /// Whether the type/length field is an 802.3 length rather than an EtherType.
+    pub fn is_8023(&self) -> bool {
+        self.get_ethertype_raw() <= MAX_8023_LENGTH
+    }
+
+    /// The 802.3 length field, or None for Ethernet II frames.
+    pub fn get_length(&self) -> Option<u16> {
+        if self.is_8023() { Some(self.get_ethertype_raw()) } else { None }
+    }
+
+    /// The 802.2 LLC PDU carried by an 802.3 frame. Ethernet II frames return Ok(None).
+    pub fn get_llc(&self) -> Result<Option<LlcPdu<'a>>, FrameError> {
+        if self.is_8023() {
+            LlcPdu::parse(self.get_payload()).map(Some)
+        }
+        else {
+            Ok(None)
+        }
+    }
+
+    /// Length of the MAC header, including VLAN tags.
+    pub fn header_len(&self) -> usize {
+        UNTAGGED_HEADER_LEN + self.tag_count * VLAN_TAG_LEN
+    }
+
+    pub fn get_header(&self) -> &'a [u8] {
+        &self.bytes[..self.header_len()]
+    }
+
+    /// Everything between the header and the FCS (if present). This includes any padding for
+    /// Ethernet II frames, while 802.3 frames stop at their length field.
+    pub fn get_payload(&self) -> &'a [u8] {
+        &self.bytes[self.header_len()..self.payload_end]
+    }
+
+    /// The network-layer packet: the payload of an Ethernet II frame, or what follows the LLC
+    /// and SNAP headers of an 802.3 frame. 802.3 frames without a valid LLC header return the
+    /// whole payload, which `get_ethertype` already refuses to name a protocol for.
+    pub fn get_network_payload(&self) -> &'a [u8] {
+        match self.get_llc() {
+            Ok(Some(llc)) => llc.get_data(),
+            _ => self.get_payload(),
+        }
+    }
+
+    pub fn get_fcs(&self) -> Option<FCS> {
+        if self.has_fcs {
+            Some(FCS::from_slice(&self.bytes[self.bytes.len() - FCS_LEN..]))
+        }
+        else {
+            None
+        }
+    }
+
+    /// Whether the trailing FCS matches the frame contents. None if the frame has no FCS.
+    pub fn verify_fcs(&self) -> Option<bool> {
+        self.get_fcs().map(|fcs| fcs.matches(&self.bytes[..self.bytes.len() - FCS_LEN]))
+    }
+
+    /// The whole frame as it was passed in.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+}
+
+/// Assembles an Ethernet II frame into wire bytes.
+///
+/// Frames shorter than the 64 byte minimum (60 bytes before the FCS) are zero padded.
+#[derive(Clone, Debug)]
+pub struct FrameBuilder {
+    dst_mac: MacAddress,
+    src_mac: MacAddress,
+    vlan_stack: VlanStack,
+    ethertype: Option<u16>,     // None writes an 802.3 length field instead.
+    payload: Vec<u8>,
+    append_fcs: bool,
+}
+
+impl FrameBuilder {
+    pub fn new(dst_mac: MacAddress, src_mac: MacAddress, proto: NetworkProtocol) -> FrameBuilder {
+        FrameBuilder::from_raw_ethertype(dst_mac, src_mac, to_ethertype(proto))
+    }
+
+    /// For EtherTypes that have no NetworkProtocol variant.
+    pub fn from_raw_ethertype(dst_mac: MacAddress, src_mac: MacAddress, ethertype: u16) -> FrameBuilder {
+        FrameBuilder { dst_mac, src_mac, vlan_stack: VlanStack::new(), ethertype: Some(ethertype), payload: Vec::new(), append_fcs: false }
+    }
+
+    /// An IEEE 802.3 frame, whose type/length field is filled in with the payload length.
+    /// The payload should start with an LLC header.
+    pub fn ieee8023(dst_mac: MacAddress, src_mac: MacAddress) -> FrameBuilder {
+        FrameBuilder { dst_mac, src_mac, vlan_stack: VlanStack::new(), ethertype: None, payload: Vec::new(), append_fcs: false }
+    }
+
+    /// Starts from a parsed frame, keeping its addresses, tags, EtherType, payload and
+    /// whether it carried an FCS. A fresh FCS is computed on build.
+    pub fn from_view(frame: &FrameView) -> FrameBuilder {
+        FrameBuilder {
+            dst_mac: frame.get_dst_mac(),
+            src_mac: frame.get_src_mac(),
+            vlan_stack: frame.get_vlan_stack(),
+            ethertype: if frame.is_8023() { None } else { Some(frame.get_ethertype_raw()) },
+            payload: frame.get_payload().to_vec(),
+            append_fcs: frame.get_fcs().is_some(),
+        }
+    }
+
+    /// Adds a VLAN tag inside any tags already added, so call this outermost first.
+    pub fn vlan_tag(mut self, tag: VLAN_Tag) -> FrameBuilder {
+        self.vlan_stack.push_inner(tag);
+        self
+    }
+
+    /// Replaces all VLAN tags.
+    pub fn vlan_stack(mut self, stack: VlanStack) -> FrameBuilder {
+        self.vlan_stack = stack;
+        self
+    }
+
+    /// For pushing, popping or rewriting tags in place.
+    pub fn get_vlan_stack_mut(&mut self) -> &mut VlanStack {
+        &mut self.vlan_stack
+    }
+
+    pub fn payload(mut self, payload: &[u8]) -> FrameBuilder {
+        self.payload = payload.to_vec();
+        self
+    }
+
+    pub fn fcs(mut self, append_fcs: bool) -> FrameBuilder {
+        self.append_fcs = append_fcs;
+        self
+    }
+
+    /// Panics if an 802.3 payload is longer than 1500 bytes.
+    pub fn build(&self) -> Vec<u8> {
+        let type_length = match self.ethertype {
+            Some(ethertype) => ethertype,
+            None => {
+                assert!(self.payload.len() <= MAX_8023_LENGTH as usize, "802.3 payload longer than 1500 bytes");
+                self.payload.len() as u16
+            },
+        };
+        let header_len = UNTAGGED_HEADER_LEN + self.vlan_stack.byte_len();
+        let mut bytes = Vec::with_capacity(cmp::max(header_len + self.payload.len(), MIN_FRAME_LEN) + FCS_LEN);
+        bytes.extend_from_slice(&self.dst_mac.to_bytes());
+        bytes.extend_from_slice(&self.src_mac.to_bytes());
+        bytes.extend_from_slice(&self.vlan_stack.to_bytes());
+        bytes.push((type_length >> 8) as u8);
+        bytes.push(type_length as u8);
+        bytes.extend_from_slice(&self.payload);
+        if bytes.len() < MIN_FRAME_LEN {
+            bytes.resize(MIN_FRAME_LEN, 0);
+        }
+        if self.append_fcs {
+            let fcs = FCS::compute(&bytes);
+            bytes.extend_from_slice(&fcs.to_bytes());
+        }
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame_bytes(tags: &[[u8;4]], ethertype: u16, payload_len: usize) -> Vec<u8> {
+        let mut bytes = vec![0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x02, 0x00, 0x00, 0x00, 0x00, 0x01];
+        for tag in tags {
+            bytes.extend_from_slice(tag);
+        }
+        bytes.push((ethertype >> 8) as u8);
+        bytes.push(ethertype as u8);
+        bytes.extend((0..payload_len).map(|x| x as u8));
+        bytes
+    }
+
+    #[test]
+    fn parses_untagged_frame() {
+        let bytes = frame_bytes(&[], 0x0800, 46);
+        let frame = FrameView::new(&bytes).unwrap();
+        assert_eq!(frame.get_dst_mac(), MacAddress::from_bytes([0xFF;6]));
+        assert_eq!(frame.get_src_mac(), MacAddress::from_bytes([0x02, 0, 0, 0, 0, 0x01]));
+        assert!(!frame.is_vlan_tagged());
+        assert_eq!(frame.get_ethertype().unwrap(), NetworkProtocol::IPv4);
+        assert_eq!(frame.get_payload().len(), 46);
+        assert!(frame.get_fcs().is_none());
+    }
+
+    #[test]
+    fn parses_double_tagged_frame_with_fcs() {
+        let bytes = frame_bytes(&[[0x88, 0xA8, 0x00, 0x64], [0x81, 0x00, 0xA0, 0x0A]], 0x86DD, 44);
+        let frame = FrameView::with_fcs(&bytes).unwrap();
+        let tags = frame.get_vlan_stack();
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags.outer().unwrap().get_vlan_id(), 100);
+        assert_eq!(tags.inner().unwrap().get_vlan_id(), 10);
+        assert_eq!(tags.inner().unwrap().get_pcp(), 5);
+        assert_eq!(frame.get_ethertype().unwrap(), NetworkProtocol::IPv6);
+        assert_eq!(frame.get_payload().len(), 40);
+        assert_eq!(frame.get_fcs().unwrap().get_value(), 0x2B2A2928);
+    }
+
+    #[test]
+    fn unknown_ethertype_is_not_a_parse_error() {
+        let bytes = frame_bytes(&[], 0x1234, 46);
+        let frame = FrameView::new(&bytes).unwrap();
+        assert_eq!(frame.get_ethertype_raw(), 0x1234);
+        assert!(frame.get_ethertype().is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_frames() {
+        assert_eq!(FrameView::new(&[0u8; 13]).unwrap_err(), FrameError::Truncated { needed: 14, actual: 13 });
+        let bytes = frame_bytes(&[], 0x8100, 0);
+        assert_eq!(FrameView::new(&bytes).unwrap_err(), FrameError::Truncated { needed: 18, actual: 14 });
+        let bytes = frame_bytes(&[], 0x0800, 2);
+        assert_eq!(FrameView::with_fcs(&bytes).unwrap_err(), FrameError::Truncated { needed: 18, actual: 16 });
+    }
+
+    #[test]
+    fn parses_8023_frames() {
+        let mut bytes = frame_bytes(&[], 6, 0);
+        bytes.extend_from_slice(&[0x42, 0x42, 0x03, 0x00, 0x00, 0x00]);
+        bytes.resize(60, 0);
+        let frame = FrameView::new(&bytes).unwrap();
+        assert!(frame.is_8023());
+        assert_eq!(frame.get_length(), Some(6));
+        assert_eq!(frame.get_payload().len(), 6);
+        assert!(frame.get_llc().unwrap().unwrap().is_stp());
+        assert!(frame.get_ethertype().is_err());
+
+        let mut bytes = frame_bytes(&[], 8, 0);
+        bytes.extend_from_slice(&[0xAA, 0xAA, 0x03, 0x00, 0x00, 0x00, 0x08, 0x06]);
+        let frame = FrameView::new(&bytes).unwrap();
+        assert_eq!(frame.get_ethertype().unwrap(), NetworkProtocol::ARP);
+        assert!(frame.get_network_payload().is_empty());
+
+        let built = FrameBuilder::ieee8023(frame.get_dst_mac(), frame.get_src_mac()).payload(frame.get_payload()).build();
+        assert_eq!(&built[..bytes.len()], &bytes[..]);
+    }
+
+    #[test]
+    fn rejects_bad_type_length_fields() {
+        let bytes = frame_bytes(&[], 0x05FF, 46);
+        assert_eq!(FrameView::new(&bytes).unwrap_err(), FrameError::InvalidTypeLength(0x05FF));
+        let bytes = frame_bytes(&[], 100, 46);
+        assert_eq!(FrameView::new(&bytes).unwrap_err(), FrameError::LengthMismatch { length: 100, available: 46 });
+        let bytes = frame_bytes(&[], 46, 46);
+        assert_eq!(FrameView::with_fcs(&bytes).unwrap_err(), FrameError::LengthMismatch { length: 46, available: 42 });
+    }
+
+    #[test]
+    fn builder_pads_and_round_trips() {
+        let dst = MacAddress::from_bytes([0x01, 0x80, 0xC2, 0, 0, 0x0E]);
+        let src = MacAddress::from_bytes([0x02, 0, 0, 0, 0, 0x01]);
+        let bytes = FrameBuilder::new(dst, src, NetworkProtocol::ARP)
+            .vlan_tag(VLAN_Tag::new(0x8100, 42))
+            .payload(&[1, 2, 3])
+            .fcs(true)
+            .build();
+        assert_eq!(bytes.len(), 64);
+
+        let frame = FrameView::with_fcs(&bytes).unwrap();
+        assert_eq!(frame.get_dst_mac(), dst);
+        assert_eq!(frame.get_src_mac(), src);
+        assert_eq!(frame.get_vlan_stack(), VlanStack::from_tags(vec![VLAN_Tag::new(0x8100, 42)]));
+        assert_eq!(frame.get_ethertype().unwrap(), NetworkProtocol::ARP);
+        assert_eq!(&frame.get_payload()[..4], &[1, 2, 3, 0]);
+        assert_eq!(frame.verify_fcs(), Some(true));
+    }
+
+    #[test]
+    fn builder_pushes_tag_onto_parsed_frame() {
+        let bytes = frame_bytes(&[[0x81, 0x00, 0x00, 0x0A]], 0x0800, 46);
+        let frame = FrameView::new(&bytes).unwrap();
+        let mut builder = FrameBuilder::from_view(&frame);
+        builder.get_vlan_stack_mut().push(VLAN_Tag::from_fields(0x9100, 0, false, 300));
+        let rebuilt = builder.build();
+
+        let frame = FrameView::new(&rebuilt).unwrap();
+        let tags = frame.get_vlan_stack();
+        assert_eq!(tags.iter().map(|x| x.get_vlan_id()).collect::<Vec<u16>>(), vec![300, 10]);
+        assert_eq!(frame.get_payload(), &bytes[18..]);
+    }
+
+    #[test]
+    fn builder_does_not_pad_large_payloads() {
+        let mac = MacAddress::from_bytes([0x02, 0, 0, 0, 0, 0x01]);
+        let bytes = FrameBuilder::new(mac, mac, NetworkProtocol::IPv4).payload(&[0xAB; 100]).build();
+        assert_eq!(bytes.len(), 114);
+    }
+}
@@ -0,0 +1,895 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+use std::iter::FromIterator;
+use std::net::IpAddr;
+use std::slice;
+use std::vec;
+
+use chrono::{DateTime,Duration,Utc};
+
+use super::mac::MacAddress;
+use super::neighbor::NeighborState;
+use super::prefix::MacPrefix;
+
+#[derive(Debug)]
+pub struct IPMACAssociateError(pub IpAddr, pub MacAddress);
+
+impl IPMACAssociateError {
+    pub fn new<I: Into<IpAddr>>(ip: I, mac: MacAddress) -> IPMACAssociateError {
+        IPMACAssociateError(ip.into(), mac)
+    }
+}
+
+impl Error for IPMACAssociateError {
+    fn description(&self) -> &str {
+        "IP and MAC Address entries could not be combined and replaced. IP or MAC address not found in set."
+    }
+}
+
+impl fmt::Display for IPMACAssociateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	    write!(f,"IP and MAC Address entries could not be combined and replaced. Both the following IP & MAC addresses must be present in the set to associate them: {} {}", self.0, self.1)
+    }
+}
+
+// Either address family. Constructors take anything that converts into an IpAddr, so
+// Ipv4Addr and Ipv6Addr can be passed directly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct IpMacCombo {
+    ip: Option<IpAddr>,
+    mac: Option<MacAddress>,
+}
+
+impl IpMacCombo {
+    pub fn new<I: Into<IpAddr>>(new_ip: I, new_mac: MacAddress) -> IpMacCombo {
+        IpMacCombo { ip: Some(new_ip.into()), mac: Some(new_mac) }
+    }
+
+    pub fn from_ip<I: Into<IpAddr>>(new_ip: I) -> IpMacCombo {
+        IpMacCombo { ip: Some(new_ip.into()), mac: None }
+    }
+
+    pub fn from_mac(new_mac: MacAddress) -> IpMacCombo {
+        IpMacCombo { ip: None, mac: Some(new_mac) }
+    }
+
+    pub fn has_ip(&self) -> bool {
+        self.ip.is_some()
+    }
+
+    pub fn has_mac(&self) -> bool {
+        self.mac.is_some()
+    }
+
+    pub fn get_mac(&self) -> Option<MacAddress> {
+        self.mac
+    }
+
+    pub fn get_ip(&self) -> Option<IpAddr> {
+        self.ip
+    }
+
+    pub fn is_ipv4(&self) -> bool {
+        self.ip.is_some_and(|x| x.is_ipv4())
+    }
+
+    pub fn is_ipv6(&self) -> bool {
+        self.ip.is_some_and(|x| x.is_ipv6())
+    }
+
+    pub fn add_ip<I: Into<IpAddr>>(&mut self, ip: I) {
+        assert!(self.ip.is_none());
+        self.ip = Some(ip.into());
+    }
+
+    pub fn add_mac(&mut self, mac: MacAddress) {
+        assert!(self.mac.is_none());
+        self.mac = Some(mac);
+    }
+}
+
+impl fmt::Display for IpMacCombo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ip = self.ip.map_or("?".to_string(), |x| x.to_string());
+        let mac = self.mac.map_or("?".to_string(), |x| x.to_string());
+        write!(f,"{} is-at {}", ip, mac)
+    }
+}
+
+// Positions in `entries` are indexed by IP, by MAC and by the whole entry, so every lookup is a
+// hash probe. Each key usually maps to one or two positions.
+fn index_add<K: Hash + Eq>(index: &mut HashMap<K, Vec<usize>>, key: K, pos: usize) {
+    index.entry(key).or_default().push(pos);
+}
+
+fn index_remove<K: Hash + Eq>(index: &mut HashMap<K, Vec<usize>>, key: K, pos: usize) {
+    let emptied = match index.get_mut(&key) {
+        Some(positions) => {
+            if let Some(i) = positions.iter().position(|x| *x == pos) {
+                positions.swap_remove(i);
+            }
+            positions.is_empty()
+        },
+        None => false,
+    };
+    if emptied {
+        index.remove(&key);
+    }
+}
+
+fn index_move<K: Hash + Eq>(index: &mut HashMap<K, Vec<usize>>, key: K, from: usize, to: usize) {
+    if let Some(pos) = index.get_mut(&key).and_then(|x| x.iter_mut().find(|x| **x == from)) {
+        *pos = to;
+    }
+}
+
+fn sorted(mut positions: Vec<usize>) -> Vec<usize> {
+    positions.sort_unstable();
+    positions
+}
+
+/// When an entry was first and last seen, how long it may go unseen before it expires, and
+/// the neighbor state a host reported for it, if it came from one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EntryInfo {
+    first_seen: DateTime<Utc>,
+    last_seen: DateTime<Utc>,
+    ttl: Option<Duration>,
+    state: Option<NeighborState>,
+}
+
+impl EntryInfo {
+    pub fn new(seen: DateTime<Utc>, ttl: Option<Duration>) -> EntryInfo {
+        EntryInfo { first_seen: seen, last_seen: seen, ttl, state: None }
+    }
+
+    pub fn from_parts(first_seen: DateTime<Utc>, last_seen: DateTime<Utc>, ttl: Option<Duration>, state: Option<NeighborState>) -> EntryInfo {
+        EntryInfo { first_seen, last_seen, ttl, state }
+    }
+
+    pub fn get_state(&self) -> Option<NeighborState> {
+        self.state
+    }
+
+    pub fn get_first_seen(&self) -> DateTime<Utc> {
+        self.first_seen
+    }
+
+    pub fn get_last_seen(&self) -> DateTime<Utc> {
+        self.last_seen
+    }
+
+    pub fn get_ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    pub fn get_age(&self, now: DateTime<Utc>) -> Duration {
+        now - self.last_seen
+    }
+
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.ttl.map(|x| self.last_seen + x)
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at().is_some_and(|x| x <= now)
+    }
+}
+
+/// IP to MAC bindings, complete or partial. Duplicate entries are allowed.
+///
+/// Removing by position with `remove`, `remove_indices`, `retain` or the expiry methods keeps
+/// the order of what is left, shifting later entries down. Removing by key, with
+/// `remove_by_ip` and `remove_by_mac` or through `learn` and `associate_ip_mac`, takes time in
+/// proportion to the entries removed instead: each hole is filled with the last entry, as
+/// `swap_remove` does. Either way positions from `get_index` and friends are only valid until
+/// the next removal. Prefer `iter`, the filters and `retain`, which don't hand out positions.
+///
+/// Every entry carries an EntryInfo. Methods without an `_at` suffix use the current time.
+#[derive(Clone, Debug)]
+pub struct IpMacSet {
+    entries: Vec<IpMacCombo>,
+    info: Vec<EntryInfo>,
+    default_ttl: Option<Duration>,
+    claim_threshold: usize,
+    by_ip: HashMap<IpAddr, Vec<usize>>,
+    by_mac: HashMap<MacAddress, Vec<usize>>,
+    by_entry: HashMap<IpMacCombo, Vec<usize>>,
+}
+
+impl Default for IpMacSet {
+    fn default() -> IpMacSet {
+        IpMacSet {
+            entries: Vec::new(),
+            info: Vec::new(),
+            default_ttl: None,
+            claim_threshold: 4,
+            by_ip: HashMap::new(),
+            by_mac: HashMap::new(),
+            by_entry: HashMap::new(),
+        }
+    }
+}
+
+impl IpMacSet {
+    pub fn new() -> IpMacSet {
+        IpMacSet::default()
+    }
+
+    /// New entries expire once unseen for `ttl`, like a kernel ARP cache.
+    pub fn with_default_ttl(ttl: Duration) -> IpMacSet {
+        IpMacSet { default_ttl: Some(ttl), ..IpMacSet::default() }
+    }
+
+    pub fn get_default_ttl(&self) -> Option<Duration> {
+        self.default_ttl
+    }
+
+    // Only applies to entries added afterwards.
+    pub fn set_default_ttl(&mut self, ttl: Option<Duration>) {
+        self.default_ttl = ttl;
+    }
+
+    /// How many distinct IPv4 addresses a MAC may hold; `observe` reports MacClaimsManyIps once
+    /// it holds more. Defaults to 4, which leaves room for a host with a few aliases. Routers and
+    /// proxy-ARP hosts may need more; 1 flags every MAC that `mac_has_multiple_ips` would.
+    pub fn get_claim_threshold(&self) -> usize {
+        self.claim_threshold
+    }
+
+    pub fn set_claim_threshold(&mut self, threshold: usize) {
+        self.claim_threshold = threshold;
+    }
+
+    pub fn push(&mut self, entry: IpMacCombo) {
+        self.push_at(entry, Utc::now());
+    }
+
+    pub fn push_at(&mut self, entry: IpMacCombo, now: DateTime<Utc>) {
+        let info = EntryInfo::new(now, self.default_ttl);
+        self.push_with_info(entry, info);
+    }
+
+    pub fn push_with_info(&mut self, entry: IpMacCombo, info: EntryInfo) {
+        let pos = self.entries.len();
+        self.entries.push(entry);
+        self.info.push(info);
+        self.index(pos);
+    }
+
+    pub fn push_ip<I: Into<IpAddr>>(&mut self, entry: I) {
+        self.push(IpMacCombo::from_ip(entry));
+    }
+
+    pub fn push_mac(&mut self, entry: MacAddress) {
+        self.push(IpMacCombo::from_mac(entry));
+    }
+
+    pub fn associate_ip_mac<I: Into<IpAddr>>(&mut self, ip: I, mac: MacAddress) -> Result<IpMacCombo, IPMACAssociateError> {
+       let ip = ip.into();
+       if !self.contains_ip(ip) || !self.contains_mac(mac) {
+           Err(IPMACAssociateError(ip,mac))
+       }
+       else {
+           let now = Utc::now();
+           let mut info = EntryInfo::new(now, self.default_ttl);
+           let mut merged = self.get_indices_by_ip(ip);
+           merged.extend(self.get_indices_by_mac(mac));
+           info.first_seen = merged.iter().map(|x| self.info[*x].first_seen).min().unwrap_or(now);
+
+           self.remove_by_ip(ip);
+           self.remove_by_mac(mac);
+
+           let ip_mac = IpMacCombo::new(ip,mac);
+           self.push_with_info(ip_mac, info);
+           Ok(ip_mac)
+       }
+    }
+
+    // Records an observed binding. IP-only and MAC-only entries it completes are dropped, but
+    // other complete bindings for the same IP or MAC are kept so conflicts stay visible.
+    // Returns false if the binding was already present, after refreshing its last-seen time.
+    pub fn learn<I: Into<IpAddr>>(&mut self, ip: I, mac: MacAddress) -> bool {
+        self.learn_at(ip, mac, Utc::now())
+    }
+
+    pub fn learn_at<I: Into<IpAddr>>(&mut self, ip: I, mac: MacAddress, now: DateTime<Utc>) -> bool {
+        let ip = ip.into();
+        let ip_mac = IpMacCombo::new(ip, mac);
+        if self.contains(&ip_mac) {
+            self.touch_at(&ip_mac, now);
+            return false;
+        }
+        self.remove_entry(&IpMacCombo::from_ip(ip));
+        self.remove_entry(&IpMacCombo::from_mac(mac));
+        self.push_at(ip_mac, now);
+        true
+    }
+
+    // Info for the first copy of `entry`.
+    pub fn get_info(&self, entry: &IpMacCombo) -> Option<&EntryInfo> {
+        self.by_entry.get(entry).and_then(|x| x.iter().min()).map(|x| &self.info[*x])
+    }
+
+    /// Marks every copy of `entry` as seen now. Returns false if there are none.
+    pub fn touch(&mut self, entry: &IpMacCombo) -> bool {
+        self.touch_at(entry, Utc::now())
+    }
+
+    pub fn touch_at(&mut self, entry: &IpMacCombo, now: DateTime<Utc>) -> bool {
+        self.update_info(entry, |info| if now > info.last_seen { info.last_seen = now; })
+    }
+
+    /// Overrides the TTL of every copy of `entry`. None keeps it forever. Returns false if
+    /// there are none.
+    pub fn set_ttl(&mut self, entry: &IpMacCombo, ttl: Option<Duration>) -> bool {
+        self.update_info(entry, |info| info.ttl = ttl)
+    }
+
+    pub fn set_state(&mut self, entry: &IpMacCombo, state: Option<NeighborState>) -> bool {
+        self.update_info(entry, |info| info.state = state)
+    }
+
+    fn update_info<F: Fn(&mut EntryInfo)>(&mut self, entry: &IpMacCombo, update: F) -> bool {
+        let positions = match self.by_entry.get(entry) {
+            Some(positions) => positions,
+            None => return false,
+        };
+        for pos in positions {
+            update(&mut self.info[*pos]);
+        }
+        true
+    }
+
+    // Positions go stale on removal, so only code in the crate that looked them up itself gets
+    // to use them.
+    pub(crate) fn get_info_by_index(&self, entry: usize) -> Option<&EntryInfo> {
+        self.info.get(entry)
+    }
+
+    // Replaces the whole EntryInfo at `entry` in place. Panics if out of bounds.
+    pub(crate) fn set_info_by_index(&mut self, entry: usize, info: EntryInfo) {
+        self.info[entry] = info;
+    }
+
+    /// Removes entries not seen within `max_age`, whatever their TTL.
+    pub fn expire_older_than(&mut self, max_age: Duration) -> Vec<IpMacCombo> {
+        self.expire_older_than_at(max_age, Utc::now())
+    }
+
+    pub fn expire_older_than_at(&mut self, max_age: Duration, now: DateTime<Utc>) -> Vec<IpMacCombo> {
+        let stale = (0..self.info.len()).filter(|x| self.info[*x].get_age(now) > max_age).collect::<Vec<usize>>();
+        self.remove_indices(stale)
+    }
+
+    /// Removes entries whose own TTL has run out.
+    pub fn expire(&mut self) -> Vec<IpMacCombo> {
+        self.expire_at(Utc::now())
+    }
+
+    pub fn expire_at(&mut self, now: DateTime<Utc>) -> Vec<IpMacCombo> {
+        let expired = (0..self.info.len()).filter(|x| self.info[*x].is_expired(now)).collect::<Vec<usize>>();
+        self.remove_indices(expired)
+    }
+
+    pub fn get(&self, entry: usize) -> Option<&IpMacCombo> {
+        self.entries.get(entry)
+    }
+
+    // Position of the first copy of `entry`. Panics if it isn't in the set.
+    pub fn get_index(&self, entry: IpMacCombo) -> usize {
+        let positions = self.by_entry.get(&entry).expect("entry is not in the IpMacSet");
+        *positions.iter().min().unwrap()
+    }
+
+    // Every position holding a copy of `entry`.
+    pub fn get_indices(&self, entry: IpMacCombo) -> Vec<usize> {
+        sorted(self.by_entry.get(&entry).cloned().unwrap_or_default())
+    }
+
+    pub fn get_indices_by_ip<I: Into<IpAddr>>(&self, ip: I) -> Vec<usize> {
+        sorted(self.by_ip.get(&ip.into()).cloned().unwrap_or_default())
+    }
+
+    pub fn get_indices_by_mac(&self, mac: MacAddress) -> Vec<usize> {
+        sorted(self.by_mac.get(&mac).cloned().unwrap_or_default())
+    }
+
+    pub fn get_by_ip<I: Into<IpAddr>>(&self, ip: I) -> Vec<&IpMacCombo> {
+        self.get_indices_by_ip(ip).into_iter().map(|x| &self.entries[x]).collect::<Vec<&IpMacCombo>>()
+    }
+
+    pub fn get_by_mac(&self, mac: MacAddress) -> Vec<&IpMacCombo> {
+        self.get_indices_by_mac(mac).into_iter().map(|x| &self.entries[x]).collect::<Vec<&IpMacCombo>>()
+    }
+
+    pub fn get_by_ip_mac<I: Into<IpAddr>>(&self, ip: I, mac: MacAddress) -> Vec<&IpMacCombo> {
+        let positions = self.by_entry.get(&IpMacCombo::new(ip, mac)).cloned().unwrap_or_default();
+        sorted(positions).into_iter().map(|x| &self.entries[x]).collect::<Vec<&IpMacCombo>>()
+    }
+
+    pub fn contains(&self, entry: &IpMacCombo) -> bool {
+        self.by_entry.contains_key(entry)
+    }
+
+    pub fn contains_ip<I: Into<IpAddr>>(&self, ip: I) -> bool {
+        self.by_ip.contains_key(&ip.into())
+    }
+
+    pub fn contains_mac(&self, mac: MacAddress) -> bool {
+        self.by_mac.contains_key(&mac)
+    }
+
+    pub fn has_multiples_of<I: Into<IpAddr>>(&self, ip: I, mac: MacAddress) -> bool {
+        self.by_entry.get(&IpMacCombo::new(ip, mac)).is_some_and(|x| x.len() > 1)
+    }
+
+    // Applies to both families: an IPv4 or IPv6 address answering from two MACs is a conflict.
+    pub fn ip_has_multiple_macs<I: Into<IpAddr>>(&self, ip: I) -> bool {
+        let mut macs = self.by_ip.get(&ip.into()).into_iter().flatten().filter_map(|x| self.entries[*x].mac);
+        match macs.next() {
+            Some(first_mac) => macs.any(|x| x != first_mac),
+            None => false,
+        }
+    }
+
+    pub fn get_ips_by_mac(&self, mac: MacAddress) -> Vec<IpAddr> {
+        self.get_by_mac(mac).iter().filter_map(|x| x.ip).collect::<Vec<IpAddr>>()
+    }
+
+    // Only counts IPv4 addresses. An IPv6 interface normally holds several at once (link-local,
+    // SLAAC, temporary), and a dual-stack host holds one of each family, so neither is a conflict.
+    pub fn mac_has_multiple_ips(&self, mac: MacAddress) -> bool {
+        let mut ips = self.by_mac.get(&mac).into_iter().flatten().filter_map(|x| self.entries[*x].ip).filter(|x| x.is_ipv4());
+        match ips.next() {
+            Some(first_ip) => ips.any(|x| x != first_ip),
+            None => false,
+        }
+    }
+
+    /// Removes the entry at `entry`, shifting every entry after it down by one. Panics if out
+    /// of bounds.
+    pub fn remove(&mut self, entry: usize) -> IpMacCombo {
+        let len = self.entries.len();
+        assert!(entry < len, "removal index {} out of bounds for IpMacSet of length {}", entry, len);
+        for pos in entry..len {
+            self.unindex(pos);
+        }
+        let removed = self.entries.remove(entry);
+        self.info.remove(entry);
+        for pos in entry..len - 1 {
+            self.index(pos);
+        }
+        removed
+    }
+
+    /// Removes the entry at `entry`, moving the last entry into its place. Unlike `remove` this
+    /// takes constant time, but does not keep the order. Panics if out of bounds.
+    pub fn swap_remove(&mut self, entry: usize) -> IpMacCombo {
+        let removed = self.entries[entry];
+        self.unindex(entry);
+        let last = self.entries.len() - 1;
+        if entry != last {
+            let moved = self.entries[last];
+            if let Some(ip) = moved.ip {
+                index_move(&mut self.by_ip, ip, last, entry);
+            }
+            if let Some(mac) = moved.mac {
+                index_move(&mut self.by_mac, mac, last, entry);
+            }
+            index_move(&mut self.by_entry, moved, last, entry);
+        }
+        self.entries.swap_remove(entry);
+        self.info.swap_remove(entry);
+        removed
+    }
+
+    /// Removes every listed position, in any order, keeping the order of what is left.
+    /// Repeated positions are removed once. The removed entries come back in ascending position
+    /// order. Panics if any position is out of bounds.
+    pub fn remove_indices(&mut self, entries: Vec<usize>) -> Vec<IpMacCombo> {
+        let mut entries = sorted(entries);
+        entries.dedup();
+        let first = match entries.first() {
+            Some(first) => *first,
+            None => return Vec::new(),
+        };
+        let len = self.entries.len();
+        assert!(entries[entries.len() - 1] < len, "removal index out of bounds for IpMacSet of length {}", len);
+
+        // Everything from the first hole on moves, so it is unindexed and compacted in one pass.
+        for pos in first..len {
+            self.unindex(pos);
+        }
+        let mut removed = Vec::with_capacity(entries.len());
+        let mut holes = entries.iter().peekable();
+        let mut kept = first;
+        for pos in first..len {
+            if holes.peek() == Some(&&pos) {
+                holes.next();
+                removed.push(self.entries[pos]);
+                continue;
+            }
+            self.entries[kept] = self.entries[pos];
+            self.info[kept] = self.info[pos];
+            kept += 1;
+        }
+        self.entries.truncate(kept);
+        self.info.truncate(kept);
+        for pos in first..kept {
+            self.index(pos);
+        }
+        removed
+    }
+
+    // Like `remove_indices`, but fills each hole with the last entry instead of shifting.
+    pub(crate) fn swap_remove_indices(&mut self, entries: Vec<usize>) -> Vec<IpMacCombo> {
+        let mut entries = sorted(entries);
+        entries.dedup();
+        // Highest first, so the entry swapped into each hole is never one still to be removed.
+        let mut removed = entries.iter().rev().map(|x| self.swap_remove(*x)).collect::<Vec<IpMacCombo>>();
+        removed.reverse();
+        removed
+    }
+
+    pub fn remove_by_ip<I: Into<IpAddr>>(&mut self, ip: I) -> Vec<IpMacCombo> {
+        let indices = self.get_indices_by_ip(ip);
+        self.swap_remove_indices(indices)
+    }
+
+    pub fn remove_by_mac(&mut self, mac: MacAddress) -> Vec<IpMacCombo> {
+        let indices = self.get_indices_by_mac(mac);
+        self.swap_remove_indices(indices)
+    }
+
+    // Removes every copy of `entry`. Returns how many there were.
+    fn remove_entry(&mut self, entry: &IpMacCombo) -> usize {
+        let indices = self.by_entry.get(entry).cloned().unwrap_or_default();
+        self.swap_remove_indices(indices).len()
+    }
+
+    fn index(&mut self, pos: usize) {
+        let entry = self.entries[pos];
+        if let Some(ip) = entry.ip {
+            index_add(&mut self.by_ip, ip, pos);
+        }
+        if let Some(mac) = entry.mac {
+            index_add(&mut self.by_mac, mac, pos);
+        }
+        index_add(&mut self.by_entry, entry, pos);
+    }
+
+    fn unindex(&mut self, pos: usize) {
+        let entry = self.entries[pos];
+        if let Some(ip) = entry.ip {
+            index_remove(&mut self.by_ip, ip, pos);
+        }
+        if let Some(mac) = entry.mac {
+            index_remove(&mut self.by_mac, mac, pos);
+        }
+        index_remove(&mut self.by_entry, entry, pos);
+    }
+}
+
+// True if `ip` falls in `network`/`prefix_len`. Addresses of the other family never do, and
+// nothing falls in a subnet whose prefix is too long for its family.
+fn in_subnet(ip: IpAddr, network: IpAddr, prefix_len: u8) -> bool {
+    match (ip, network) {
+        (IpAddr::V4(_), IpAddr::V4(_)) if prefix_len > 32 => false,
+        (IpAddr::V6(_), IpAddr::V6(_)) if prefix_len > 128 => false,
+        (IpAddr::V4(ip), IpAddr::V4(network)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix_len as u32).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(network) & mask
+        },
+        (IpAddr::V6(ip), IpAddr::V6(network)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix_len as u32).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(network) & mask
+        },
+        _ => false,
+    }
+}
+
+/// Borrowing iterator over the entries of an IpMacSet, in position order.
+#[derive(Clone, Debug)]
+pub struct IpMacIter<'a> {
+    entries: slice::Iter<'a, IpMacCombo>,
+}
+
+impl<'a> Iterator for IpMacIter<'a> {
+    type Item = &'a IpMacCombo;
+
+    fn next(&mut self) -> Option<&'a IpMacCombo> {
+        self.entries.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for IpMacIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.entries.next_back()
+    }
+}
+
+impl ExactSizeIterator for IpMacIter<'_> {}
+
+impl IpMacSet {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> IpMacIter<'_> {
+        IpMacIter { entries: self.entries.iter() }
+    }
+
+    pub fn iter_with_info(&self) -> impl Iterator<Item = (&IpMacCombo, &EntryInfo)> + '_ {
+        self.entries.iter().zip(self.info.iter())
+    }
+
+    /// Keeps only the entries for which `keep` returns true, in their current order.
+    pub fn retain<F: FnMut(&IpMacCombo) -> bool>(&mut self, mut keep: F) {
+        self.retain_with_info(|entry, _| keep(entry));
+    }
+
+    /// Like `retain`, but `keep` also sees each entry's EntryInfo.
+    pub fn retain_with_info<F: FnMut(&IpMacCombo, &EntryInfo) -> bool>(&mut self, mut keep: F) {
+        let dropped = (0..self.entries.len()).filter(|x| !keep(&self.entries[*x], &self.info[*x])).collect::<Vec<usize>>();
+        self.remove_indices(dropped);
+    }
+
+    // Entries with both an IP and a MAC.
+    pub fn complete(&self) -> impl Iterator<Item = &IpMacCombo> + '_ {
+        self.iter().filter(|x| x.has_ip() && x.has_mac())
+    }
+
+    pub fn ip_only(&self) -> impl Iterator<Item = &IpMacCombo> + '_ {
+        self.iter().filter(|x| !x.has_mac())
+    }
+
+    pub fn mac_only(&self) -> impl Iterator<Item = &IpMacCombo> + '_ {
+        self.iter().filter(|x| !x.has_ip())
+    }
+
+    /// Entries whose IP lies in `network`/`prefix_len`, e.g. 192.168.1.0/24. A prefix length
+    /// too long for the network's address family, such as /33 for IPv4, matches nothing.
+    pub fn in_subnet<I: Into<IpAddr>>(&self, network: I, prefix_len: u8) -> impl Iterator<Item = &IpMacCombo> + '_ {
+        let network = network.into();
+        self.iter().filter(move |x| x.get_ip().is_some_and(|ip| in_subnet(ip, network, prefix_len)))
+    }
+
+    pub fn with_oui(&self, oui: [u8;3]) -> impl Iterator<Item = &IpMacCombo> + '_ {
+        self.iter().filter(move |x| x.get_mac().is_some_and(|mac| mac.get_oui() == oui))
+    }
+
+    pub fn in_mac_prefix(&self, prefix: MacPrefix) -> impl Iterator<Item = &IpMacCombo> + '_ {
+        self.iter().filter(move |x| x.get_mac().is_some_and(|mac| prefix.contains(&mac)))
+    }
+}
+
+impl<'a> IntoIterator for &'a IpMacSet {
+    type Item = &'a IpMacCombo;
+    type IntoIter = IpMacIter<'a>;
+
+    fn into_iter(self) -> IpMacIter<'a> {
+        self.iter()
+    }
+}
+
+impl IntoIterator for IpMacSet {
+    type Item = IpMacCombo;
+    type IntoIter = vec::IntoIter<IpMacCombo>;
+
+    fn into_iter(self) -> vec::IntoIter<IpMacCombo> {
+        self.entries.into_iter()
+    }
+}
+
+// Entries added without info are stamped with the current time and the default TTL.
+impl Extend<IpMacCombo> for IpMacSet {
+    fn extend<T: IntoIterator<Item = IpMacCombo>>(&mut self, iter: T) {
+        let now = Utc::now();
+        for entry in iter {
+            self.push_at(entry, now);
+        }
+    }
+}
+
+impl Extend<(IpMacCombo, EntryInfo)> for IpMacSet {
+    fn extend<T: IntoIterator<Item = (IpMacCombo, EntryInfo)>>(&mut self, iter: T) {
+        for (entry, info) in iter {
+            self.push_with_info(entry, info);
+        }
+    }
+}
+
+impl FromIterator<IpMacCombo> for IpMacSet {
+    fn from_iter<T: IntoIterator<Item = IpMacCombo>>(iter: T) -> IpMacSet {
+        let mut set = IpMacSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl FromIterator<(IpMacCombo, EntryInfo)> for IpMacSet {
+    fn from_iter<T: IntoIterator<Item = (IpMacCombo, EntryInfo)>>(iter: T) -> IpMacSet {
+        let mut set = IpMacSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::test_util::mac;
+    use std::net::{Ipv4Addr,Ipv6Addr};
+
+    #[test]
+    fn indexes_follow_removals() {
+        let mut set = IpMacSet::new();
+        for i in 0..10u8 {
+            set.push(IpMacCombo::new(Ipv4Addr::new(10, 0, 0, i), mac(i)));
+        }
+        set.push_ip(Ipv4Addr::new(10, 0, 0, 3));
+        assert_eq!(set.get_indices_by_ip(Ipv4Addr::new(10, 0, 0, 3)), vec![3, 10]);
+
+        let removed = set.remove_indices(vec![7, 2, 9, 2]);
+        assert_eq!(removed.iter().map(|x| x.get_mac().unwrap()).collect::<Vec<MacAddress>>(), vec![mac(2), mac(7), mac(9)]);
+        assert!(!set.contains_mac(mac(7)) && !set.contains_ip(Ipv4Addr::new(10, 0, 0, 9)));
+        for i in [0u8, 1, 3, 4, 5, 6, 8].iter() {
+            let index = set.get_index(IpMacCombo::new(Ipv4Addr::new(10, 0, 0, *i), mac(*i)));
+            assert_eq!(set.get(index).unwrap().get_mac(), Some(mac(*i)));
+        }
+        assert_eq!(set.get_by_ip(Ipv4Addr::new(10, 0, 0, 3)).len(), 2);
+        let order = set.iter().filter_map(|x| x.get_mac()).collect::<Vec<MacAddress>>();
+        assert_eq!(order, [0u8, 1, 3, 4, 5, 6, 8].iter().map(|x| mac(*x)).collect::<Vec<MacAddress>>());
+
+        assert_eq!(set.remove(1).get_mac(), Some(mac(1)));
+        assert_eq!(set.get(1).unwrap().get_mac(), Some(mac(3)));
+        assert_eq!(set.get_indices_by_mac(mac(8)), vec![5]);
+        assert_eq!(set.get_indices_by_ip(Ipv4Addr::new(10, 0, 0, 3)), vec![1, 6]);
+
+        assert_eq!(set.swap_remove(1).get_mac(), Some(mac(3)));
+        assert_eq!(set.get(1), Some(&IpMacCombo::from_ip(Ipv4Addr::new(10, 0, 0, 3))));
+        assert_eq!(set.get_indices_by_ip(Ipv4Addr::new(10, 0, 0, 3)), vec![1]);
+        assert_eq!(set.len(), 6);
+
+        // Keyed removal only moves the last entry into the hole.
+        assert_eq!(set.remove_by_mac(mac(4)), vec![IpMacCombo::new(Ipv4Addr::new(10, 0, 0, 4), mac(4))]);
+        let order = set.iter().filter_map(|x| x.get_mac()).collect::<Vec<MacAddress>>();
+        assert_eq!(order, vec![mac(0), mac(8), mac(5), mac(6)]);
+        assert_eq!(set.get_indices_by_mac(mac(8)), vec![2]);
+        assert_eq!(set.get_indices_by_ip(Ipv4Addr::new(10, 0, 0, 3)), vec![1]);
+    }
+
+    #[test]
+    fn keeps_query_semantics() {
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+        let mut set = IpMacSet::new();
+        set.push_ip(ip);
+        set.push_mac(mac(1));
+        assert!(set.associate_ip_mac(ip, mac(1)).is_ok());
+        assert!(set.associate_ip_mac(ip, mac(2)).is_err());
+        assert_eq!(set.get_by_ip(ip), vec![&IpMacCombo::new(ip, mac(1))]);
+
+        set.push(IpMacCombo::new(ip, mac(1)));
+        assert!(set.has_multiples_of(ip, mac(1)));
+        assert!(!set.ip_has_multiple_macs(ip));
+        set.push(IpMacCombo::new(ip, mac(2)));
+        assert!(set.ip_has_multiple_macs(ip));
+        assert!(!set.mac_has_multiple_ips(mac(1)));
+
+        assert_eq!(set.remove_by_ip(ip).len(), 3);
+        assert!(!set.contains_mac(mac(1)) && !set.contains_mac(mac(2)));
+        assert!(set.get(0).is_none());
+    }
+
+    #[test]
+    fn handles_ipv6_neighbors() {
+        let v4 = Ipv4Addr::new(192, 168, 1, 10);
+        let link_local = mac(1).to_ipv6_link_local();
+        let global = mac(1).to_ipv6_slaac("2001:db8::".parse().unwrap());
+        let temporary: Ipv6Addr = "2001:db8::1234:5678".parse().unwrap();
+
+        let mut set = IpMacSet::new();
+        set.push_ip(link_local);
+        set.push_mac(mac(1));
+        let combo = set.associate_ip_mac(link_local, mac(1)).unwrap();
+        assert!(combo.is_ipv6() && !combo.is_ipv4());
+        assert!(set.learn(global, mac(1)) && set.learn(temporary, mac(1)) && set.learn(v4, mac(1)));
+        assert_eq!(set.get_ips_by_mac(mac(1)).len(), 4);
+        assert!(!set.mac_has_multiple_ips(mac(1)));
+        assert!(set.learn(Ipv4Addr::new(192, 168, 1, 11), mac(1)));
+        assert!(set.mac_has_multiple_ips(mac(1)));
+
+        assert!(!set.ip_has_multiple_macs(global));
+        set.learn(global, mac(2));
+        assert!(set.ip_has_multiple_macs(IpAddr::V6(global)));
+    }
+
+    #[test]
+    fn ages_entries() {
+        let start = Utc::now();
+        let ip = Ipv4Addr::new(10, 1, 1, 1);
+        let mut set = IpMacSet::with_default_ttl(Duration::seconds(60));
+        set.learn_at(ip, mac(1), start);
+        set.learn_at(Ipv4Addr::new(10, 1, 1, 2), mac(2), start);
+        set.push_at(IpMacCombo::from_mac(mac(3)), start);
+        assert!(set.set_ttl(&IpMacCombo::from_mac(mac(3)), None));
+        assert!(!set.set_ttl(&IpMacCombo::from_mac(mac(9)), None));
+
+        assert!(!set.learn_at(ip, mac(1), start + Duration::seconds(45)));
+        let info = *set.get_info(&IpMacCombo::new(ip, mac(1))).unwrap();
+        assert_eq!((info.get_first_seen(), info.get_last_seen()), (start, start + Duration::seconds(45)));
+        assert_eq!(info.expires_at(), Some(start + Duration::seconds(105)));
+
+        assert_eq!(set.expire_at(start + Duration::seconds(90)), vec![IpMacCombo::new(Ipv4Addr::new(10, 1, 1, 2), mac(2))]);
+        assert_eq!(set.expire_older_than_at(Duration::seconds(30), start + Duration::seconds(90)).len(), 2);
+        assert!(set.get(0).is_none());
+    }
+
+    #[test]
+    fn association_keeps_first_seen() {
+        let start = Utc::now();
+        let ip = Ipv4Addr::new(10, 1, 1, 1);
+        let mut set = IpMacSet::new();
+        set.push_at(IpMacCombo::from_mac(mac(1)), start);
+        set.push_at(IpMacCombo::from_ip(ip), start + Duration::seconds(5));
+        set.associate_ip_mac(ip, mac(1)).unwrap();
+        let info = set.get_info(&IpMacCombo::new(ip, mac(1))).unwrap();
+        assert_eq!(info.get_first_seen(), start);
+        assert!(info.get_last_seen() > start && info.get_ttl().is_none());
+    }
+
+    #[test]
+    fn behaves_like_a_collection() {
+        let mut set = vec![
+            IpMacCombo::new(Ipv4Addr::new(192, 168, 1, 1), mac(1)),
+            IpMacCombo::new(Ipv4Addr::new(192, 168, 2, 1), MacAddress::from_bytes([0x00, 0x50, 0x56, 0, 0, 1])),
+            IpMacCombo::from_ip(Ipv4Addr::new(192, 168, 1, 9)),
+            IpMacCombo::from_mac(mac(3)),
+            IpMacCombo::new("fe80::1".parse::<Ipv6Addr>().unwrap(), mac(1)),
+        ].into_iter().collect::<IpMacSet>();
+        assert_eq!(set.len(), 5);
+        assert_eq!(set.iter().len(), 5);
+        assert_eq!(set.iter().next_back(), Some(&IpMacCombo::new("fe80::1".parse::<Ipv6Addr>().unwrap(), mac(1))));
+
+        assert_eq!(set.complete().count(), 3);
+        assert_eq!(set.ip_only().collect::<Vec<&IpMacCombo>>(), vec![&IpMacCombo::from_ip(Ipv4Addr::new(192, 168, 1, 9))]);
+        assert_eq!(set.mac_only().count(), 1);
+        assert_eq!(set.in_subnet(Ipv4Addr::new(192, 168, 1, 0), 24).count(), 2);
+        assert_eq!(set.in_subnet(Ipv4Addr::new(0, 0, 0, 0), 0).count(), 3);
+        assert_eq!(set.in_subnet("fe80::".parse::<Ipv6Addr>().unwrap(), 10).count(), 1);
+        assert_eq!(set.in_subnet(Ipv4Addr::new(192, 168, 1, 1), 32).count(), 1);
+        assert_eq!(set.in_subnet(Ipv4Addr::new(192, 168, 1, 1), 33).count(), 0);
+        assert_eq!(set.in_subnet("fe80::1".parse::<Ipv6Addr>().unwrap(), 128).count(), 1);
+        assert_eq!(set.in_subnet("fe80::1".parse::<Ipv6Addr>().unwrap(), 129).count(), 0);
+        assert_eq!(set.with_oui([0x00, 0x50, 0x56]).count(), 1);
+        assert_eq!(set.in_mac_prefix("02:00:00:00:00:00/40".parse().unwrap()).count(), 3);
+
+        set.retain(|entry| entry.has_ip() && entry.has_mac());
+        assert_eq!(set.len(), 3);
+        assert!(set.ip_only().next().is_none() && set.mac_only().next().is_none());
+        assert_eq!(set.get_by_mac(mac(1)).len(), 2);
+        let last = set.len() - 1;
+        let stale = *set.get(last).unwrap();
+        set.set_state(&stale, Some(NeighborState::Stale));
+        let mut fresh = set.clone();
+        fresh.retain_with_info(|_, info| info.get_state() != Some(NeighborState::Stale));
+        assert_eq!(fresh.iter().collect::<Vec<&IpMacCombo>>(), set.iter().take(last).collect::<Vec<&IpMacCombo>>());
+
+        set.extend(vec![IpMacCombo::from_mac(mac(4))]);
+        assert!(set.contains_mac(mac(4)));
+        assert_eq!((&set).into_iter().count(), 4);
+        assert_eq!(set.into_iter().filter(|x| x.is_ipv6()).count(), 1);
+        assert!(IpMacSet::new().is_empty());
+    }
+}
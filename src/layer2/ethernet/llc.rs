@@ -0,0 +1,283 @@
+use std::fmt;
+
+use layer3::{NetworkProtocol,NetworkProtocolNotRecognizedError};
+use super::{FrameError,parse_ethertype};
+
+const LLC_HEADER_LEN: usize = 3;
+const SNAP_HEADER_LEN: usize = 5;
+
+pub const SAP_SNAP: u8 = 0xAA;
+pub const OUI_RFC1042: [u8;3] = [0x00, 0x00, 0x00];
+pub const OUI_802_1H: [u8;3] = [0x00, 0x00, 0xF8];
+pub const OUI_CISCO: [u8;3] = [0x00, 0x00, 0x0C];
+pub const CISCO_PID_CDP: u16 = 0x2000;
+pub const CISCO_PID_VTP: u16 = 0x2003;
+pub const CISCO_PID_DTP: u16 = 0x2004;
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LlcSap {
+    Null,
+    LLC_Sublayer_Mgmt,
+    SNA_Path_Control,
+    IPv4,
+    Spanning_Tree,
+    SNAP,
+    IPX,
+    NetBIOS,
+    ISO_Network_Layer,
+    Global,
+}
+
+impl fmt::Display for LlcSap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self,f)
+    }
+}
+
+// Keys are SAP values with the I/G (DSAP) or C/R (SSAP) bit cleared, except Global.
+u8_key_phf_map! {
+    LlcSaps -> LlcSap = {
+        0x00 => LlcSap::Null,
+        0x02 => LlcSap::LLC_Sublayer_Mgmt,
+        0x04 => LlcSap::SNA_Path_Control,
+        0x06 => LlcSap::IPv4,
+        0x42 => LlcSap::Spanning_Tree,
+        0xAA => LlcSap::SNAP,
+        0xE0 => LlcSap::IPX,
+        0xF0 => LlcSap::NetBIOS,
+        0xFE => LlcSap::ISO_Network_Layer,
+        0xFF => LlcSap::Global,
+    }
+}
+
+pub fn parse_sap(sap: u8) -> Option<LlcSap> {
+    if sap == 0xFF {
+        Some(LlcSap::Global)
+    }
+    else {
+        LlcSaps(sap & 0xFE).cloned()
+    }
+}
+
+/// IEEE 802.2 LLC header. The control field is one byte for unnumbered (U-format) PDUs and
+/// two bytes for information and supervisory PDUs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LlcHeader {
+    dsap: u8,
+    ssap: u8,
+    control: u16,
+    extended_control: bool,
+}
+
+impl LlcHeader {
+    // Unnumbered PDUs only, which is all that connectionless LLC traffic uses.
+    pub fn new(dsap: u8, ssap: u8, control: u8) -> LlcHeader {
+        LlcHeader { dsap, ssap, control: control as u16, extended_control: false }
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<LlcHeader, FrameError> {
+        if bytes.len() < LLC_HEADER_LEN {
+            return Err(FrameError::Truncated { needed: LLC_HEADER_LEN, actual: bytes.len() });
+        }
+        let extended_control = bytes[2] & 0x03 != 0x03;
+        let control = if !extended_control {
+            bytes[2] as u16
+        }
+        else if bytes.len() < LLC_HEADER_LEN + 1 {
+            return Err(FrameError::Truncated { needed: LLC_HEADER_LEN + 1, actual: bytes.len() });
+        }
+        else {
+            ((bytes[2] as u16) << 8) + (bytes[3] as u16)
+        };
+        Ok(LlcHeader { dsap: bytes[0], ssap: bytes[1], control, extended_control })
+    }
+
+    pub fn get_dsap(&self) -> u8 {
+        self.dsap
+    }
+
+    pub fn get_ssap(&self) -> u8 {
+        self.ssap
+    }
+
+    pub fn get_control(&self) -> u16 {
+        self.control
+    }
+
+    pub fn get_dsap_name(&self) -> Option<LlcSap> {
+        parse_sap(self.dsap)
+    }
+
+    pub fn get_ssap_name(&self) -> Option<LlcSap> {
+        parse_sap(self.ssap)
+    }
+
+    // I/G bit of the DSAP.
+    pub fn is_group_dsap(&self) -> bool {
+        self.dsap & 0x01 == 0x01
+    }
+
+    // C/R bit of the SSAP.
+    pub fn is_response(&self) -> bool {
+        self.ssap & 0x01 == 0x01
+    }
+
+    pub fn is_unnumbered(&self) -> bool {
+        !self.extended_control
+    }
+
+    pub fn is_snap(&self) -> bool {
+        self.dsap == SAP_SNAP && self.ssap == SAP_SNAP && !self.extended_control && self.control == 0x03
+    }
+
+    /// Length of the header on the wire.
+    pub fn byte_len(&self) -> usize {
+        if self.extended_control { LLC_HEADER_LEN + 1 } else { LLC_HEADER_LEN }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        if self.is_unnumbered() {
+            vec![self.dsap, self.ssap, self.control as u8]
+        }
+        else {
+            vec![self.dsap, self.ssap, (self.control >> 8) as u8, self.control as u8]
+        }
+    }
+}
+
+/// SNAP extension following an LLC header with DSAP and SSAP 0xAA.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SnapHeader {
+    oui: [u8;3],
+    pid: u16,
+}
+
+impl SnapHeader {
+    pub fn new(oui: [u8;3], pid: u16) -> SnapHeader {
+        SnapHeader { oui, pid }
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<SnapHeader, FrameError> {
+        if bytes.len() < SNAP_HEADER_LEN {
+            return Err(FrameError::Truncated { needed: SNAP_HEADER_LEN, actual: bytes.len() });
+        }
+        Ok(SnapHeader { oui: [bytes[0], bytes[1], bytes[2]], pid: ((bytes[3] as u16) << 8) + (bytes[4] as u16) })
+    }
+
+    pub fn get_oui(&self) -> [u8;3] {
+        self.oui
+    }
+
+    pub fn get_pid(&self) -> u16 {
+        self.pid
+    }
+
+    /// Whether the PID is an EtherType, i.e. the OUI is 00-00-00 or the 802.1H 00-00-F8.
+    pub fn carries_ethertype(&self) -> bool {
+        self.oui == OUI_RFC1042 || self.oui == OUI_802_1H
+    }
+
+    /// Resolves the PID through the EtherType registry. Fails for vendor-specific OUIs.
+    pub fn get_protocol(&self) -> Result<NetworkProtocol, NetworkProtocolNotRecognizedError> {
+        if self.carries_ethertype() {
+            parse_ethertype(self.pid)
+        }
+        else {
+            Err(NetworkProtocolNotRecognizedError(self.pid))
+        }
+    }
+
+    pub fn is_cdp(&self) -> bool {
+        self.oui == OUI_CISCO && self.pid == CISCO_PID_CDP
+    }
+
+    pub fn to_bytes(&self) -> [u8;5] {
+        [self.oui[0], self.oui[1], self.oui[2], (self.pid >> 8) as u8, self.pid as u8]
+    }
+}
+
+/// A borrowed view of an 802.2 LLC PDU, with its SNAP header if it has one.
+#[derive(Clone, Copy, Debug)]
+pub struct LlcPdu<'a> {
+    header: LlcHeader,
+    snap: Option<SnapHeader>,
+    data: &'a [u8],
+}
+
+impl<'a> LlcPdu<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<LlcPdu<'a>, FrameError> {
+        let header = LlcHeader::parse(bytes)?;
+        let mut offset = header.byte_len();
+        let snap = if header.is_snap() {
+            let snap = SnapHeader::parse(&bytes[offset..]).map_err(|_| FrameError::Truncated { needed: offset + SNAP_HEADER_LEN, actual: bytes.len() })?;
+            offset += SNAP_HEADER_LEN;
+            Some(snap)
+        }
+        else {
+            None
+        };
+        Ok(LlcPdu { header, snap, data: &bytes[offset..] })
+    }
+
+    pub fn get_header(&self) -> LlcHeader {
+        self.header
+    }
+
+    pub fn get_snap(&self) -> Option<SnapHeader> {
+        self.snap
+    }
+
+    /// Everything after the LLC and SNAP headers.
+    pub fn get_data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub fn is_stp(&self) -> bool {
+        self.header.get_dsap_name() == Some(LlcSap::Spanning_Tree)
+    }
+
+    pub fn is_cdp(&self) -> bool {
+        self.snap.is_some_and(|x| x.is_cdp())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_stp_bpdu() {
+        let bytes = [0x42, 0x42, 0x03, 0x00, 0x00, 0x00];
+        let pdu = LlcPdu::parse(&bytes).unwrap();
+        assert!(pdu.is_stp());
+        assert!(pdu.get_header().is_unnumbered());
+        assert!(pdu.get_snap().is_none());
+        assert_eq!(pdu.get_data(), &[0x00, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn parses_snap_headers() {
+        let cdp = [0xAA, 0xAA, 0x03, 0x00, 0x00, 0x0C, 0x20, 0x00, 0x02];
+        let pdu = LlcPdu::parse(&cdp).unwrap();
+        assert!(pdu.is_cdp());
+        assert!(pdu.get_snap().unwrap().get_protocol().is_err());
+        assert_eq!(pdu.get_data(), &[0x02]);
+
+        let ip = [0xAA, 0xAA, 0x03, 0x00, 0x00, 0x00, 0x08, 0x00];
+        let pdu = LlcPdu::parse(&ip).unwrap();
+        assert_eq!(pdu.get_snap().unwrap().get_protocol().unwrap(), NetworkProtocol::IPv4);
+
+        assert_eq!(LlcPdu::parse(&ip[..6]).unwrap_err(), FrameError::Truncated { needed: 8, actual: 6 });
+    }
+
+    #[test]
+    fn two_byte_control_field() {
+        let header = LlcHeader::parse(&[0xF0, 0xF1, 0x00, 0x02]).unwrap();
+        assert!(!header.is_unnumbered());
+        assert!(header.is_response());
+        assert_eq!(header.get_control(), 0x0002);
+        assert_eq!(header.get_dsap_name(), Some(LlcSap::NetBIOS));
+        assert_eq!(header.to_bytes(), vec![0xF0, 0xF1, 0x00, 0x02]);
+    }
+}
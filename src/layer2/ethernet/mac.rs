@@ -0,0 +1,651 @@
+use std::error::Error;
+use std::fmt;
+use std::convert::TryFrom;
+use std::net::{IpAddr,Ipv4Addr,Ipv6Addr};
+use std::str::FromStr;
+
+pub const MAC_BROADCAST: [u8;6] = [0xFF;6];
+pub const MAC_STP: [u8;6] = [0x01, 0x80, 0xC2, 0x00, 0x00, 0x00];              // Also the nearest customer bridge
+pub const MAC_NEAREST_NON_TPMR: [u8;6] = [0x01, 0x80, 0xC2, 0x00, 0x00, 0x03];
+pub const MAC_NEAREST_BRIDGE: [u8;6] = [0x01, 0x80, 0xC2, 0x00, 0x00, 0x0E];
+const IPV4_MULTICAST_OUI: [u8;3] = [0x01, 0x00, 0x5E];
+const IPV6_MULTICAST_PREFIX: [u8;2] = [0x33, 0x33];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacAddressParseError(pub String);
+
+// Returned by TryFrom<&[u8]> when the slice is the wrong size. Holds the length supplied.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MacAddressLengthError(pub usize);
+
+impl Error for MacAddressLengthError {
+    fn description(&self) -> &str {
+        "Slice is the wrong length for a hardware address."
+    }
+}
+
+impl fmt::Display for MacAddressLengthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"MAC addresses are 6 bytes and EUI-64s are 8 bytes. Length: {}", self.0)
+    }
+}
+
+impl MacAddressParseError {
+    pub fn new(input: &str) -> MacAddressParseError {
+        MacAddressParseError(input.to_string())
+    }
+}
+
+impl Error for MacAddressParseError {
+    fn description(&self) -> &str {
+        "Supplied string is not a valid MAC address."
+    }
+}
+
+impl fmt::Display for MacAddressParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"Supplied string is not a valid MAC address: {:?}", self.0)
+    }
+}
+
+// Accepts colon or dash separated octets of one or two digits, Cisco style dotted groups of
+// four digits, or bare hex. Separators can't be mixed. Case doesn't matter.
+fn parse_hw_address(input: &str, out: &mut [u8]) -> Result<(), MacAddressParseError> {
+    let err = || MacAddressParseError::new(input);
+    let is_hex = |x: &str| !x.is_empty() && x.chars().all(|c| c.is_ascii_hexdigit());
+
+    let separator = if input.contains(':') { Some(':') }
+                    else if input.contains('-') { Some('-') }
+                    else if input.contains('.') { Some('.') }
+                    else { None };
+    match separator {
+        Some('.') => {
+            let groups = input.split('.').collect::<Vec<&str>>();
+            if groups.len() * 2 != out.len() || groups.iter().any(|x| x.len() != 4 || !is_hex(x)) {
+                return Err(err());
+            }
+            for (i, group) in groups.iter().enumerate() {
+                let val = u16::from_str_radix(group, 16).map_err(|_| err())?;
+                out[i * 2] = (val >> 8) as u8;
+                out[i * 2 + 1] = val as u8;
+            }
+        },
+        Some(sep) => {
+            let octets = input.split(sep).collect::<Vec<&str>>();
+            if octets.len() != out.len() || octets.iter().any(|x| x.len() > 2 || !is_hex(x)) {
+                return Err(err());
+            }
+            for (i, octet) in octets.iter().enumerate() {
+                out[i] = u8::from_str_radix(octet, 16).map_err(|_| err())?;
+            }
+        },
+        None => {
+            if input.len() != out.len() * 2 || !is_hex(input) {
+                return Err(err());
+            }
+            for (i, byte) in out.iter_mut().enumerate() {
+                *byte = u8::from_str_radix(&input[i * 2..i * 2 + 2], 16).map_err(|_| err())?;
+            }
+        },
+    }
+    Ok(())
+}
+
+fn format_hw_address(bytes: &[u8], format: MacFormat) -> String {
+    let hex = bytes.iter().map(|x| if format.uppercase { format!("{:02X}", x) } else { format!("{:02x}", x) }).collect::<Vec<String>>();
+    match format.separator {
+        MacSeparator::Colon => hex.join(":"),
+        MacSeparator::Dash => hex.join("-"),
+        MacSeparator::Dot => hex.chunks(2).map(|x| x.concat()).collect::<Vec<String>>().join("."),
+        MacSeparator::None => hex.concat(),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MacSeparator {
+    Colon,      // 12:34:56:78:90:ab
+    Dash,       // 12-34-56-78-90-ab
+    Dot,        // 1234.5678.90ab
+    None,       // 1234567890ab
+}
+
+/// How to print a MacAddress or Eui64. The default matches Display: upper case with colons.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MacFormat {
+    pub separator: MacSeparator,
+    pub uppercase: bool,
+}
+
+impl MacFormat {
+    pub fn new(separator: MacSeparator, uppercase: bool) -> MacFormat {
+        MacFormat { separator, uppercase }
+    }
+
+    // Linux style, as printed by `ip link`.
+    pub fn unix() -> MacFormat {
+        MacFormat::new(MacSeparator::Colon, false)
+    }
+
+    // As printed by Windows `ipconfig`.
+    pub fn windows() -> MacFormat {
+        MacFormat::new(MacSeparator::Dash, true)
+    }
+
+    pub fn cisco() -> MacFormat {
+        MacFormat::new(MacSeparator::Dot, false)
+    }
+
+    pub fn bare() -> MacFormat {
+        MacFormat::new(MacSeparator::None, false)
+    }
+}
+
+impl Default for MacFormat {
+    fn default() -> MacFormat {
+        MacFormat::new(MacSeparator::Colon, true)
+    }
+}
+
+// Ordering is numeric, first octet most significant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MacAddress {
+    addr: [u8;6],
+}
+
+impl MacAddress {
+
+    pub fn from_bytes(bytes: [u8;6]) -> MacAddress {
+        MacAddress { addr: bytes }
+    }
+
+    pub fn from_slice(slice: &[u8]) -> MacAddress {
+        assert!(slice.len() == 6);
+        MacAddress { addr: [slice[0],slice[1],slice[2],slice[3],slice[4],slice[5]] }
+    }
+
+    pub fn from_vec(vector: Vec<u8>) -> MacAddress {
+        MacAddress::from_slice(vector.as_slice())
+    }
+
+    pub fn to_bytes(&self) -> [u8;6] {
+        self.addr
+    }
+
+    pub fn get_oui(&self) -> [u8;3] {
+        [self.addr[0], self.addr[1], self.addr[2]]
+    }
+
+    /// The address as a 48 bit integer, first octet most significant.
+    pub fn to_u64(&self) -> u64 {
+        self.addr.iter().fold(0u64, |acc, x| (acc << 8) | *x as u64)
+    }
+
+    /// Only the low 48 bits of `value` are used.
+    pub fn from_u64(value: u64) -> MacAddress {
+        let bytes = value.to_be_bytes();
+        MacAddress::from_slice(&bytes[2..])
+    }
+
+    /// MAC-48 encapsulation: FF:FF between the OUI and the rest. Same as `to_eui64_mac48`.
+    pub fn to_eui64(&self) -> Eui64 {
+        self.to_eui64_mac48()
+    }
+
+    pub fn to_eui64_mac48(&self) -> Eui64 {
+        Eui64 { addr: [self.addr[0], self.addr[1], self.addr[2], 0xFF, 0xFF, self.addr[3], self.addr[4], self.addr[5]] }
+    }
+
+    /// EUI-48 encapsulation: FF:FE between the OUI and the rest.
+    pub fn to_eui64_eui48(&self) -> Eui64 {
+        Eui64 { addr: [self.addr[0], self.addr[1], self.addr[2], 0xFF, 0xFE, self.addr[3], self.addr[4], self.addr[5]] }
+    }
+
+    /// The RFC 4291 interface identifier: EUI-48 encapsulation with the U/L bit inverted.
+    pub fn to_modified_eui64(&self) -> Eui64 {
+        let mut eui = self.to_eui64_eui48();
+        eui.addr[0] ^= 0x02;
+        eui
+    }
+
+    /// Reverses `to_modified_eui64`. None unless the identifier carries the FF:FE filler.
+    pub fn from_modified_eui64(iid: &Eui64) -> Option<MacAddress> {
+        let mut eui = *iid;
+        eui.addr[0] ^= 0x02;
+        eui.to_mac_address_eui48()
+    }
+
+    /// SLAAC address: the first 64 bits of `prefix` followed by the modified EUI-64.
+    pub fn to_ipv6_slaac(&self, prefix: Ipv6Addr) -> Ipv6Addr {
+        let mut octets = prefix.octets();
+        octets[8..].copy_from_slice(&self.to_modified_eui64().addr);
+        Ipv6Addr::from(octets)
+    }
+
+    pub fn to_ipv6_link_local(&self) -> Ipv6Addr {
+        self.to_ipv6_slaac(Ipv6Addr::new(0xFE80, 0, 0, 0, 0, 0, 0, 0))
+    }
+
+    /// The MAC an EUI-64 based SLAAC or link-local address was derived from. None if the
+    /// interface identifier wasn't built from a MAC (privacy or stable-opaque addresses).
+    pub fn from_ipv6_slaac(addr: Ipv6Addr) -> Option<MacAddress> {
+        MacAddress::from_modified_eui64(&Eui64::from_slice(&addr.octets()[8..]))
+    }
+
+    pub fn to_formatted_string(&self, format: MacFormat) -> String {
+        format_hw_address(&self.addr, format)
+    }
+
+    pub fn broadcast() -> MacAddress {
+        MacAddress { addr: MAC_BROADCAST }
+    }
+
+    // I/G bit: least significant bit of the first octet.
+    pub fn is_multicast(&self) -> bool {
+        self.addr[0] & 0x01 != 0
+    }
+
+    pub fn is_unicast(&self) -> bool {
+        !self.is_multicast()
+    }
+
+    pub fn is_broadcast(&self) -> bool {
+        self.addr == MAC_BROADCAST
+    }
+
+    pub fn is_nil(&self) -> bool {
+        self.addr == [0;6]
+    }
+
+    // U/L bit: second least significant bit of the first octet.
+    pub fn is_local(&self) -> bool {
+        self.addr[0] & 0x02 != 0
+    }
+
+    pub fn is_universal(&self) -> bool {
+        !self.is_local()
+    }
+
+    /// 01:80:C2:00:00:00 through 01:80:C2:00:00:0F, which 802.1D bridges never forward.
+    pub fn is_ieee_reserved(&self) -> bool {
+        self.addr[..5] == MAC_STP[..5] && self.addr[5] <= 0x0F
+    }
+
+    pub fn is_stp(&self) -> bool {
+        self.addr == MAC_STP
+    }
+
+    /// Any of the three destinations 802.1AB allows for LLDPDUs.
+    pub fn is_lldp(&self) -> bool {
+        self.addr == MAC_NEAREST_BRIDGE || self.addr == MAC_NEAREST_NON_TPMR || self.addr == MAC_STP
+    }
+
+    /// 01:00:5E followed by the low 23 bits of the group. None if `group` isn't multicast.
+    pub fn from_ipv4_multicast(group: Ipv4Addr) -> Option<MacAddress> {
+        if !group.is_multicast() {
+            return None;
+        }
+        let octets = group.octets();
+        Some(MacAddress { addr: [IPV4_MULTICAST_OUI[0], IPV4_MULTICAST_OUI[1], IPV4_MULTICAST_OUI[2], octets[1] & 0x7F, octets[2], octets[3]] })
+    }
+
+    /// 33:33 followed by the low 32 bits of the group. None if `group` isn't multicast.
+    pub fn from_ipv6_multicast(group: Ipv6Addr) -> Option<MacAddress> {
+        if !group.is_multicast() {
+            return None;
+        }
+        let octets = group.octets();
+        Some(MacAddress { addr: [IPV6_MULTICAST_PREFIX[0], IPV6_MULTICAST_PREFIX[1], octets[12], octets[13], octets[14], octets[15]] })
+    }
+
+    pub fn from_ip_multicast(group: IpAddr) -> Option<MacAddress> {
+        match group {
+            IpAddr::V4(ip) => MacAddress::from_ipv4_multicast(ip),
+            IpAddr::V6(ip) => MacAddress::from_ipv6_multicast(ip),
+        }
+    }
+
+    pub fn is_ipv4_multicast(&self) -> bool {
+        self.addr[..3] == IPV4_MULTICAST_OUI && self.addr[3] & 0x80 == 0
+    }
+
+    pub fn is_ipv6_multicast(&self) -> bool {
+        self.addr[..2] == IPV6_MULTICAST_PREFIX
+    }
+
+    /// The 32 IPv4 groups that share this MAC, since five bits of the group are lost in the
+    /// mapping. Empty if this isn't an IPv4 multicast MAC.
+    pub fn to_ipv4_multicast_groups(&self) -> Vec<Ipv4Addr> {
+        if !self.is_ipv4_multicast() {
+            return Vec::new();
+        }
+        (0..32u8).map(|x| Ipv4Addr::new(0xE0 | (x >> 1), ((x & 0x01) << 7) | self.addr[3], self.addr[4], self.addr[5])).collect()
+    }
+
+    /// The low 32 bits every IPv6 group mapping to this MAC ends with. The upper 96 bits are
+    /// unrecoverable, so use `matches_multicast_group` to test a specific group.
+    pub fn get_ipv6_multicast_suffix(&self) -> Option<[u8;4]> {
+        if !self.is_ipv6_multicast() {
+            return None;
+        }
+        Some([self.addr[2], self.addr[3], self.addr[4], self.addr[5]])
+    }
+
+    pub fn matches_multicast_group(&self, group: IpAddr) -> bool {
+        MacAddress::from_ip_multicast(group).is_some_and(|x| x.addr == self.addr)
+    }
+}
+
+impl FromStr for MacAddress {
+    type Err = MacAddressParseError;
+
+    fn from_str(s: &str) -> Result<MacAddress, MacAddressParseError> {
+        let mut addr = [0u8;6];
+        parse_hw_address(s, &mut addr)?;
+        Ok(MacAddress { addr })
+    }
+}
+
+impl fmt::Display for MacAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(&self.to_formatted_string(MacFormat::default()))
+    }
+}
+
+impl fmt::LowerHex for MacAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(&self.to_formatted_string(MacFormat::unix()))
+    }
+}
+
+impl fmt::UpperHex for MacAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(&self.to_formatted_string(MacFormat::default()))
+    }
+}
+
+impl From<[u8;6]> for MacAddress {
+    fn from(bytes: [u8;6]) -> MacAddress {
+        MacAddress::from_bytes(bytes)
+    }
+}
+
+impl From<MacAddress> for [u8;6] {
+    fn from(mac: MacAddress) -> [u8;6] {
+        mac.addr
+    }
+}
+
+impl TryFrom<&[u8]> for MacAddress {
+    type Error = MacAddressLengthError;
+
+    fn try_from(slice: &[u8]) -> Result<MacAddress, MacAddressLengthError> {
+        if slice.len() != 6 {
+            return Err(MacAddressLengthError(slice.len()));
+        }
+        Ok(MacAddress::from_slice(slice))
+    }
+}
+
+impl AsRef<[u8]> for MacAddress {
+    fn as_ref(&self) -> &[u8] {
+        &self.addr
+    }
+}
+
+#[allow(dead_code)]
+type Eui48 = MacAddress;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Eui64 {
+    addr: [u8;8],
+}
+
+impl Eui64 {
+
+    pub fn from_bytes(bytes: [u8;8]) -> Eui64 {
+        Eui64 { addr: bytes }
+    }
+
+    pub fn from_slice(slice: &[u8]) -> Eui64 {
+        assert!(slice.len() == 8);
+        Eui64 { addr: [slice[0],slice[1],slice[2],slice[3],slice[4],slice[5],slice[6],slice[7]] }
+    }
+
+    pub fn from_vec(vector: Vec<u8>) -> Eui64 {
+        Eui64::from_slice(vector.as_slice())
+    }
+
+    pub fn from_mac_address(mac: MacAddress) -> Eui64 {
+        mac.to_eui64()
+    }
+
+    /// Same formats as MacAddress, with eight octets (or four dotted groups).
+    pub fn from_string(str: &str) -> Result<Eui64, MacAddressParseError> {
+        str.parse()
+    }
+
+    pub fn to_bytes(&self) -> [u8;8] {
+        self.addr
+    }
+
+    pub fn get_oui(&self) -> [u8;3] {
+        [self.addr[0], self.addr[1], self.addr[2]]
+    }
+
+    pub fn is_mac48_encapsulation(&self) -> bool {
+        self.addr[3] == 0xFF && self.addr[4] == 0xFF
+    }
+
+    pub fn is_eui48_encapsulation(&self) -> bool {
+        self.addr[3] == 0xFF && self.addr[4] == 0xFE
+    }
+
+    /// Strips either filler. None if the identifier doesn't encapsulate a 48 bit address.
+    pub fn to_mac_address(&self) -> Option<MacAddress> {
+        if self.is_mac48_encapsulation() || self.is_eui48_encapsulation() {
+            Some(self.strip_filler())
+        } else {
+            None
+        }
+    }
+
+    pub fn to_mac_address_mac48(&self) -> Option<MacAddress> {
+        if self.is_mac48_encapsulation() { Some(self.strip_filler()) } else { None }
+    }
+
+    pub fn to_mac_address_eui48(&self) -> Option<MacAddress> {
+        if self.is_eui48_encapsulation() { Some(self.strip_filler()) } else { None }
+    }
+
+    fn strip_filler(&self) -> MacAddress {
+        MacAddress { addr: [self.addr[0], self.addr[1], self.addr[2], self.addr[5], self.addr[6], self.addr[7]] }
+    }
+
+    pub fn to_formatted_string(&self, format: MacFormat) -> String {
+        format_hw_address(&self.addr, format)
+    }
+}
+
+impl FromStr for Eui64 {
+    type Err = MacAddressParseError;
+
+    fn from_str(s: &str) -> Result<Eui64, MacAddressParseError> {
+        let mut addr = [0u8;8];
+        parse_hw_address(s, &mut addr)?;
+        Ok(Eui64 { addr })
+    }
+}
+
+impl fmt::Display for Eui64 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(&self.to_formatted_string(MacFormat::default()))
+    }
+}
+
+impl fmt::LowerHex for Eui64 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(&self.to_formatted_string(MacFormat::unix()))
+    }
+}
+
+impl fmt::UpperHex for Eui64 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(&self.to_formatted_string(MacFormat::default()))
+    }
+}
+
+impl From<[u8;8]> for Eui64 {
+    fn from(bytes: [u8;8]) -> Eui64 {
+        Eui64::from_bytes(bytes)
+    }
+}
+
+impl From<Eui64> for [u8;8] {
+    fn from(eui: Eui64) -> [u8;8] {
+        eui.addr
+    }
+}
+
+impl TryFrom<&[u8]> for Eui64 {
+    type Error = MacAddressLengthError;
+
+    fn try_from(slice: &[u8]) -> Result<Eui64, MacAddressLengthError> {
+        if slice.len() != 8 {
+            return Err(MacAddressLengthError(slice.len()));
+        }
+        Ok(Eui64::from_slice(slice))
+    }
+}
+
+impl AsRef<[u8]> for Eui64 {
+    fn as_ref(&self) -> &[u8] {
+        &self.addr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_format() {
+        let expected = MacAddress::from_bytes([0x12, 0x34, 0x56, 0x78, 0x90, 0xAB]);
+        for input in &["12:34:56:78:90:AB", "12-34-56-78-90-ab", "1234.5678.90aB", "1234567890ab"] {
+            assert_eq!(input.parse::<MacAddress>().unwrap(), expected);
+        }
+        assert_eq!("0:1b:21:a:b:c".parse::<MacAddress>().unwrap(), MacAddress::from_bytes([0x00, 0x1B, 0x21, 0x0A, 0x0B, 0x0C]));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        for input in &["", "12:34:56:78:90", "12:34:56:78:90:AB:CD", "12:34-56:78:90:AB", "123:4:56:78:90:AB",
+                       "12:34:56:78:90:GG", "12::56:78:90:AB", "1234.5678.90a", "1234567890a", "+1234567890a"] {
+            assert_eq!(input.parse::<MacAddress>(), Err(MacAddressParseError::new(input)), "{}", input);
+        }
+    }
+
+    #[test]
+    fn formats() {
+        let mac = MacAddress::from_bytes([0x12, 0x34, 0x56, 0x78, 0x90, 0xAB]);
+        assert_eq!(mac.to_string(), "12:34:56:78:90:AB");
+        assert_eq!(mac.to_formatted_string(MacFormat::unix()), "12:34:56:78:90:ab");
+        assert_eq!(mac.to_formatted_string(MacFormat::windows()), "12-34-56-78-90-AB");
+        assert_eq!(mac.to_formatted_string(MacFormat::cisco()), "1234.5678.90ab");
+        assert_eq!(mac.to_formatted_string(MacFormat::new(MacSeparator::None, true)), "1234567890AB");
+    }
+
+    #[test]
+    fn classifies() {
+        let mac = MacAddress::from_bytes([0x00, 0x1B, 0x21, 0x0A, 0x0B, 0x0C]);
+        assert!(mac.is_unicast() && mac.is_universal() && !mac.is_broadcast());
+        assert!(MacAddress::from_bytes([0x02, 0, 0, 0, 0, 1]).is_local());
+        assert!(MacAddress::broadcast().is_broadcast() && MacAddress::broadcast().is_multicast());
+        assert!(MacAddress::from_bytes(MAC_NEAREST_BRIDGE).is_lldp());
+        assert!(MacAddress::from_bytes(MAC_STP).is_stp());
+        assert!(MacAddress::from_bytes([0x01, 0x80, 0xC2, 0, 0, 0x02]).is_ieee_reserved());
+        assert!(!MacAddress::from_bytes([0x01, 0x80, 0xC2, 0, 0, 0x10]).is_ieee_reserved());
+    }
+
+    #[test]
+    fn maps_multicast_groups() {
+        let group = Ipv4Addr::new(239, 129, 2, 3);
+        let mac = MacAddress::from_ipv4_multicast(group).unwrap();
+        assert_eq!(mac.to_string(), "01:00:5E:01:02:03");
+        let groups = mac.to_ipv4_multicast_groups();
+        assert_eq!(groups.len(), 32);
+        assert!(groups.contains(&group) && groups.contains(&Ipv4Addr::new(224, 1, 2, 3)));
+        assert!(groups.iter().all(|x| MacAddress::from_ipv4_multicast(*x) == Some(mac)));
+        assert_eq!(MacAddress::from_ipv4_multicast(Ipv4Addr::new(10, 0, 0, 1)), None);
+
+        let group: Ipv6Addr = "ff02::1:ff00:1234".parse().unwrap();
+        let mac = MacAddress::from_ipv6_multicast(group).unwrap();
+        assert_eq!(mac.to_string(), "33:33:FF:00:12:34");
+        assert_eq!(mac.get_ipv6_multicast_suffix(), Some([0xFF, 0x00, 0x12, 0x34]));
+        assert!(mac.matches_multicast_group(IpAddr::V6(group)));
+        assert!(mac.to_ipv4_multicast_groups().is_empty());
+    }
+
+    #[test]
+    fn converts_eui64() {
+        let mac = MacAddress::from_bytes([0x00, 0x1B, 0x21, 0x0A, 0x0B, 0x0C]);
+        assert_eq!(mac.to_eui64_mac48().to_formatted_string(MacFormat::unix()), "00:1b:21:ff:ff:0a:0b:0c");
+        assert_eq!(mac.to_modified_eui64().to_formatted_string(MacFormat::unix()), "02:1b:21:ff:fe:0a:0b:0c");
+        assert_eq!(mac.to_eui64_mac48().to_mac_address(), Some(mac));
+        assert_eq!(mac.to_eui64_mac48().to_mac_address_eui48(), None);
+        assert_eq!(MacAddress::from_modified_eui64(&mac.to_modified_eui64()), Some(mac));
+        assert_eq!(Eui64::from_bytes([0, 1, 2, 3, 4, 5, 6, 7]).to_mac_address(), None);
+    }
+
+    #[test]
+    fn derives_slaac_addresses() {
+        let mac = MacAddress::from_bytes([0x52, 0x54, 0x00, 0x12, 0x34, 0x56]);
+        assert_eq!(mac.to_ipv6_link_local(), "fe80::5054:ff:fe12:3456".parse::<Ipv6Addr>().unwrap());
+        let addr = mac.to_ipv6_slaac("2001:db8:1:2:aaaa::".parse().unwrap());
+        assert_eq!(addr, "2001:db8:1:2:5054:ff:fe12:3456".parse::<Ipv6Addr>().unwrap());
+        assert_eq!(MacAddress::from_ipv6_slaac(addr), Some(mac));
+        assert_eq!(MacAddress::from_ipv6_slaac("2001:db8::1".parse().unwrap()), None);
+    }
+
+    #[test]
+    fn keys_maps_and_sorts() {
+        use std::collections::{BTreeMap,HashMap};
+
+        let low = MacAddress::from([0x00, 0x00, 0x00, 0x00, 0x00, 0xFF]);
+        let high = MacAddress::from([0x00, 0x00, 0x00, 0x00, 0x01, 0x00]);
+        assert!(low < high);
+        let mut by_mac = HashMap::new();
+        by_mac.insert(high, "high");
+        by_mac.insert(low, "low");
+        assert_eq!(by_mac[&low], "low");
+        let sorted = [high, low].iter().map(|x| (*x, ())).collect::<BTreeMap<MacAddress, ()>>();
+        assert_eq!(sorted.keys().next(), Some(&low));
+
+        let eui: Eui64 = "00:11:22:ff:fe:33:44:55".parse().unwrap();
+        let mut by_eui = HashMap::new();
+        by_eui.insert(eui, 1);
+        assert_eq!(by_eui[&Eui64::from([0x00, 0x11, 0x22, 0xFF, 0xFE, 0x33, 0x44, 0x55])], 1);
+    }
+
+    #[test]
+    fn converts_and_prints() {
+        let bytes: &[u8] = &[0x00, 0x1B, 0x21, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E];
+        let mac = MacAddress::try_from(&bytes[..6]).unwrap();
+        assert_eq!(MacAddress::try_from(bytes), Err(MacAddressLengthError(8)));
+        assert_eq!(mac.as_ref(), &bytes[..6]);
+        assert_eq!(<[u8;6]>::from(mac), mac.to_bytes());
+        assert_eq!(format!("{:x} {:X}", mac, mac), "00:1b:21:0a:0b:0c 00:1B:21:0A:0B:0C");
+        assert_eq!(format!("[{:>18}]", mac), "[ 00:1B:21:0A:0B:0C]");
+
+        let eui = Eui64::try_from(bytes).unwrap();
+        assert_eq!(Eui64::try_from(&bytes[..6]), Err(MacAddressLengthError(6)));
+        assert_eq!(eui.to_string(), "00:1B:21:0A:0B:0C:0D:0E");
+        assert_eq!(format!("{:x}", eui), "00:1b:21:0a:0b:0c:0d:0e");
+    }
+
+    #[test]
+    fn parses_eui64() {
+        let eui = Eui64::from_string("0011.22ff.fe33.4455").unwrap();
+        assert_eq!(eui.to_formatted_string(MacFormat::unix()), "00:11:22:ff:fe:33:44:55");
+        assert!(Eui64::from_string("00:11:22:33:44:55").is_err());
+    }
+}
@@ -0,0 +1,94 @@
+#![macro_use]
+extern crate phf;
+
+use layer3::{NetworkProtocol,NetworkProtocolNotRecognizedError};
+
+mod diff;
+mod events;
+mod fcs;
+mod frame;
+mod ipmac;
+mod llc;
+mod mac;
+mod neighbor;
+#[cfg(feature = "oui-registry")]
+mod oui;
+mod prefix;
+mod random;
+mod snapshot;
+mod vlan;
+
+pub use self::diff::{IpMacDiff,BindingChange,MergePolicy};
+pub use self::events::{BindingEvent,BindingEventKind};
+pub use self::fcs::FCS;
+pub use self::frame::{FrameView,FrameBuilder,FrameError};
+pub use self::ipmac::{IpMacCombo,IpMacSet,IpMacIter,EntryInfo,IPMACAssociateError};
+pub use self::mac::{MacAddress,Eui64,MacFormat,MacSeparator,MacAddressParseError,MacAddressLengthError};
+pub use self::mac::{MAC_BROADCAST,MAC_STP,MAC_NEAREST_NON_TPMR,MAC_NEAREST_BRIDGE};
+#[cfg(feature = "oui-registry")]
+pub use self::oui::{Vendor,OuiBlock,lookup_vendor,MaLargeVendors,MaMediumVendors,MaSmallVendors};
+pub use self::neighbor::{NeighborEntry,NeighborState,NeighborParseError,NeighborParseErrorKind};
+pub use self::neighbor::{parse_proc_net_arp,parse_ip_neigh,parse_arp_an,write_proc_net_arp,write_ip_neigh,write_arp_an};
+pub use self::prefix::{MacPrefix,MacRange,MacRangeIter,MacPrefixSplit,MacRangeSplit,MacPrefixError};
+pub use self::random::MacGenerator;
+pub use self::snapshot::{Snapshot,SnapshotError,SNAPSHOT_VERSION};
+pub use self::llc::{LlcHeader,LlcPdu,LlcSap,SnapHeader,LlcSaps,parse_sap};
+pub use self::llc::{SAP_SNAP,OUI_RFC1042,OUI_802_1H,OUI_CISCO,CISCO_PID_CDP,CISCO_PID_VTP,CISCO_PID_DTP};
+pub use self::vlan::{VLAN_Tag,VlanStack,TPID_CUSTOMER,TPID_SERVICE,TPID_LEGACY_QINQ,is_vlan_tpid};
+
+// Defined by IANA as "IEEE 802 Numbers": https://www.iana.org/assignments/ieee-802-numbers/ieee-802-numbers.xhtml
+u16_key_phf_map! {
+    NetworkProtocols -> NetworkProtocol = {
+	    0x0800 => NetworkProtocol::IPv4,
+	    0x0806 => NetworkProtocol::ARP,
+	    0x0842 => NetworkProtocol::Wake_on_LAN,
+	    0x8035 => NetworkProtocol::Reverse_ARP,
+	    0x809B => NetworkProtocol::AppleTalk,
+	    0x80F3 => NetworkProtocol::AppleTalk_ARP,
+	    0x8100 => NetworkProtocol::VLAN_Tagged,
+	    0x8137 => NetworkProtocol::IPX,
+	    0x86DD => NetworkProtocol::IPv6,
+	    0x889A => NetworkProtocol::Hyper_SCSI,
+	    0x88A2 => NetworkProtocol::ATAoE,
+        0x88A8 => NetworkProtocol::Double_VLAN_Tagged,
+	    0x88CC => NetworkProtocol::LLDP,
+	    0x88E5 => NetworkProtocol::MAC_Sec,
+	    0x8906 => NetworkProtocol::FCoE,
+    }
+}
+
+pub fn parse_ethertype(etype: u16) -> Result<NetworkProtocol,NetworkProtocolNotRecognizedError> {
+    NetworkProtocols(etype).cloned().ok_or(NetworkProtocolNotRecognizedError(etype))
+}
+
+// The reverse of parse_ethertype. Keep in sync with NetworkProtocols above.
+pub fn to_ethertype(proto: NetworkProtocol) -> u16 {
+    match proto {
+        NetworkProtocol::IPv4 => 0x0800,
+        NetworkProtocol::ARP => 0x0806,
+        NetworkProtocol::Wake_on_LAN => 0x0842,
+        NetworkProtocol::Reverse_ARP => 0x8035,
+        NetworkProtocol::AppleTalk => 0x809B,
+        NetworkProtocol::AppleTalk_ARP => 0x80F3,
+        NetworkProtocol::VLAN_Tagged => 0x8100,
+        NetworkProtocol::IPX => 0x8137,
+        NetworkProtocol::IPv6 => 0x86DD,
+        NetworkProtocol::Hyper_SCSI => 0x889A,
+        NetworkProtocol::ATAoE => 0x88A2,
+        NetworkProtocol::Double_VLAN_Tagged => 0x88A8,
+        NetworkProtocol::LLDP => 0x88CC,
+        NetworkProtocol::MAC_Sec => 0x88E5,
+        NetworkProtocol::FCoE => 0x8906,
+    }
+}
+
+// Fixtures shared by the IpMacSet test modules.
+#[cfg(test)]
+mod test_util {
+    use super::MacAddress;
+
+    // A locally administered MAC that differs only in its last byte.
+    pub fn mac(last: u8) -> MacAddress {
+        MacAddress::from_bytes([0x02, 0, 0, 0, 0, last])
+    }
+}
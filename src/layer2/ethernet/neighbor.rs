@@ -0,0 +1,455 @@
+use std::error::Error;
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use chrono::Utc;
+
+use super::ipmac::{IpMacCombo,IpMacSet};
+use super::mac::{MacAddress,MacFormat};
+
+/* src/layer2/ethernet/neighbor.rs
+ *
+ * Description: Reads and writes the three ways a Linux host shows its neighbor table:
+ * /proc/net/arp, `ip neigh show` and `arp -an`. Parsers stop at the first bad line and say
+ * which one it was, but skip well-formed rows for link types other than Ethernet. Writers
+ * print lower case MACs like the real tools.
+ *
+ */
+
+// /proc/net/arp flags, from include/uapi/linux/if_arp.h
+const ATF_COM: u32 = 0x02;
+const ATF_PERM: u32 = 0x04;
+const ARPHRD_ETHER: u32 = 0x1;
+
+const PROC_NET_ARP_HEADER: &str = "IP address       HW type     Flags       HW address            Mask     Device";
+const PROC_NET_ARP_COLUMNS: [&str; 6] = ["IP address", "HW type", "Flags", "HW address", "Mask", "Device"];
+
+/// Linux neighbor (NUD) states, as `ip neigh` prints them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NeighborState {
+    Incomplete,
+    Reachable,
+    Stale,
+    Delay,
+    Probe,
+    Failed,
+    NoArp,
+    Permanent,
+}
+
+impl NeighborState {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            NeighborState::Incomplete => "INCOMPLETE",
+            NeighborState::Reachable => "REACHABLE",
+            NeighborState::Stale => "STALE",
+            NeighborState::Delay => "DELAY",
+            NeighborState::Probe => "PROBE",
+            NeighborState::Failed => "FAILED",
+            NeighborState::NoArp => "NOARP",
+            NeighborState::Permanent => "PERMANENT",
+        }
+    }
+
+    // The kernel's NUD_VALID: states where the link-layer address can be used.
+    pub fn is_valid(&self) -> bool {
+        !matches!(*self, NeighborState::Incomplete | NeighborState::Failed)
+    }
+
+    fn from_proc_flags(flags: u32) -> NeighborState {
+        if flags & ATF_PERM != 0 { NeighborState::Permanent }
+        else if flags & ATF_COM != 0 { NeighborState::Reachable }
+        else { NeighborState::Incomplete }
+    }
+
+    fn to_proc_flags(self) -> u32 {
+        match self {
+            NeighborState::Permanent => ATF_COM | ATF_PERM,
+            NeighborState::Incomplete | NeighborState::Failed => 0,
+            _ => ATF_COM,
+        }
+    }
+}
+
+impl FromStr for NeighborState {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<NeighborState, ()> {
+        match s {
+            "INCOMPLETE" => Ok(NeighborState::Incomplete),
+            "REACHABLE" => Ok(NeighborState::Reachable),
+            "STALE" => Ok(NeighborState::Stale),
+            "DELAY" => Ok(NeighborState::Delay),
+            "PROBE" => Ok(NeighborState::Probe),
+            "FAILED" => Ok(NeighborState::Failed),
+            "NOARP" => Ok(NeighborState::NoArp),
+            "PERMANENT" => Ok(NeighborState::Permanent),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for NeighborState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NeighborParseErrorKind {
+    MissingField(&'static str),
+    InvalidIp,
+    InvalidMac,
+    InvalidHwType,
+    InvalidFlags,
+    UnknownState,
+    UnexpectedToken,
+}
+
+/// Which line (counting from 1) failed to parse, what it said, and why.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NeighborParseError {
+    pub line: usize,
+    pub content: String,
+    pub kind: NeighborParseErrorKind,
+}
+
+impl NeighborParseError {
+    pub fn new(line: usize, content: &str, kind: NeighborParseErrorKind) -> NeighborParseError {
+        NeighborParseError { line, content: content.to_string(), kind }
+    }
+}
+
+impl Error for NeighborParseError {
+    fn description(&self) -> &str {
+        "Neighbor table line could not be parsed."
+    }
+}
+
+impl fmt::Display for NeighborParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"Neighbor table line {} could not be parsed ({:?}): {:?}", self.line, self.kind, self.content)
+    }
+}
+
+/// One line of a neighbor table. Entries without a MAC are INCOMPLETE or FAILED lookups.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NeighborEntry {
+    ip: IpAddr,
+    mac: Option<MacAddress>,
+    device: Option<String>,
+    state: NeighborState,
+}
+
+impl NeighborEntry {
+    pub fn new<I: Into<IpAddr>>(ip: I, mac: Option<MacAddress>, device: Option<&str>, state: NeighborState) -> NeighborEntry {
+        NeighborEntry { ip: ip.into(), mac, device: device.map(|x| x.to_string()), state }
+    }
+
+    pub fn get_ip(&self) -> IpAddr {
+        self.ip
+    }
+
+    pub fn get_mac(&self) -> Option<MacAddress> {
+        self.mac
+    }
+
+    pub fn get_device(&self) -> Option<&str> {
+        self.device.as_deref()
+    }
+
+    pub fn get_state(&self) -> NeighborState {
+        self.state
+    }
+
+    pub fn to_combo(&self) -> IpMacCombo {
+        match self.mac {
+            Some(mac) => IpMacCombo::new(self.ip, mac),
+            None => IpMacCombo::from_ip(self.ip),
+        }
+    }
+
+    fn device_or_dash(&self) -> &str {
+        self.device.as_deref().unwrap_or("-")
+    }
+
+    fn mac_lower(&self) -> Option<String> {
+        self.mac.map(|x| x.to_formatted_string(MacFormat::unix()))
+    }
+}
+
+fn data_lines(input: &str) -> impl Iterator<Item = (usize, &str)> {
+    input.lines().enumerate().map(|(i, x)| (i + 1, x.trim())).filter(|x| !x.1.is_empty())
+}
+
+fn parse_ip(token: Option<&str>, line: usize, content: &str) -> Result<IpAddr, NeighborParseError> {
+    let token = token.ok_or_else(|| NeighborParseError::new(line, content, NeighborParseErrorKind::MissingField("IP address")))?;
+    token.parse().map_err(|_| NeighborParseError::new(line, content, NeighborParseErrorKind::InvalidIp))
+}
+
+fn parse_mac(token: &str, line: usize, content: &str) -> Result<MacAddress, NeighborParseError> {
+    token.parse().map_err(|_| NeighborParseError::new(line, content, NeighborParseErrorKind::InvalidMac))
+}
+
+fn parse_hex(token: &str) -> Option<u32> {
+    u32::from_str_radix(token.trim_start_matches("0x"), 16).ok()
+}
+
+/// Parses /proc/net/arp. The header line is optional. Rows for other hardware types, such as
+/// InfiniBand with its 20 byte addresses, are skipped.
+pub fn parse_proc_net_arp(input: &str) -> Result<Vec<NeighborEntry>, NeighborParseError> {
+    let mut entries = Vec::new();
+    for (line, content) in data_lines(input) {
+        if content.starts_with("IP address") {
+            continue;
+        }
+        let fields = content.split_whitespace().collect::<Vec<&str>>();
+        if let Some(missing) = PROC_NET_ARP_COLUMNS.get(fields.len()) {
+            return Err(NeighborParseError::new(line, content, NeighborParseErrorKind::MissingField(missing)));
+        }
+        let ip = parse_ip(Some(fields[0]), line, content)?;
+        let hw_type = parse_hex(fields[1]).ok_or_else(|| NeighborParseError::new(line, content, NeighborParseErrorKind::InvalidHwType))?;
+        if hw_type != ARPHRD_ETHER {
+            continue;
+        }
+        let flags = parse_hex(fields[2]).ok_or_else(|| NeighborParseError::new(line, content, NeighborParseErrorKind::InvalidFlags))?;
+        let mac = parse_mac(fields[3], line, content)?;
+        let state = NeighborState::from_proc_flags(flags);
+        let mac = if state.is_valid() && !mac.is_nil() { Some(mac) } else { None };
+        entries.push(NeighborEntry::new(ip, mac, Some(fields[5]), state));
+    }
+    Ok(entries)
+}
+
+/// Parses `ip neigh show` output, for either family. Flags like `router` and `proxy` and
+/// statistics from `ip -s` are skipped.
+pub fn parse_ip_neigh(input: &str) -> Result<Vec<NeighborEntry>, NeighborParseError> {
+    let mut entries = Vec::new();
+    for (line, content) in data_lines(input) {
+        let mut tokens = content.split_whitespace();
+        let ip = parse_ip(tokens.next(), line, content)?;
+        let (mut mac, mut device, mut state) = (None, None, None);
+        while let Some(token) = tokens.next() {
+            match token {
+                "dev" | "lladdr" | "proto" | "probes" | "used" | "vrf" => {
+                    let value = tokens.next().ok_or_else(|| NeighborParseError::new(line, content, NeighborParseErrorKind::MissingField("value")))?;
+                    match token {
+                        "dev" => device = Some(value),
+                        "lladdr" => mac = Some(parse_mac(value, line, content)?),
+                        _ => (),
+                    }
+                },
+                "router" | "proxy" | "extern_learn" | "offload" | "managed" | "ref" => (),
+                _ if token.chars().all(|x| x.is_ascii_uppercase()) => {
+                    state = Some(token.parse::<NeighborState>().map_err(|_| NeighborParseError::new(line, content, NeighborParseErrorKind::UnknownState))?);
+                },
+                // `ref 2` style counters from ip -s
+                _ if token.chars().all(|x| x.is_ascii_digit()) => (),
+                _ => return Err(NeighborParseError::new(line, content, NeighborParseErrorKind::UnexpectedToken)),
+            }
+        }
+        let state = state.ok_or_else(|| NeighborParseError::new(line, content, NeighborParseErrorKind::MissingField("state")))?;
+        // As in /proc/net/arp, a leftover lladdr on a FAILED or INCOMPLETE entry isn't usable.
+        let mac = mac.filter(|x| state.is_valid() && !x.is_nil());
+        entries.push(NeighborEntry::new(ip, mac, device, state));
+    }
+    Ok(entries)
+}
+
+/// Parses `arp -an` (or `arp -a`; host names are ignored).
+pub fn parse_arp_an(input: &str) -> Result<Vec<NeighborEntry>, NeighborParseError> {
+    let mut entries = Vec::new();
+    for (line, content) in data_lines(input) {
+        let missing = |field| NeighborParseError::new(line, content, NeighborParseErrorKind::MissingField(field));
+        let mut tokens = content.split_whitespace().skip(1);
+        let ip = tokens.next().and_then(|x| x.strip_prefix('(')).and_then(|x| x.strip_suffix(')'));
+        let ip = parse_ip(ip, line, content)?;
+        if tokens.next() != Some("at") {
+            return Err(missing("at"));
+        }
+        let mac = match tokens.next() {
+            Some("<incomplete>") => None,
+            Some(token) => Some(parse_mac(token, line, content)?),
+            None => return Err(missing("HW address")),
+        };
+        let mut state = if mac.is_some() { NeighborState::Reachable } else { NeighborState::Incomplete };
+        let mut device = None;
+        while let Some(token) = tokens.next() {
+            match token {
+                "on" => device = Some(tokens.next().ok_or_else(|| missing("Device"))?),
+                "PERM" => state = NeighborState::Permanent,
+                "[ether]" | "PUB" | "CM" => (),
+                _ => return Err(NeighborParseError::new(line, content, NeighborParseErrorKind::UnexpectedToken)),
+            }
+        }
+        entries.push(NeighborEntry::new(ip, mac, device, state));
+    }
+    Ok(entries)
+}
+
+/// Writes /proc/net/arp, header included, in the kernel's column layout. IPv6 entries are
+/// left out since the file is IPv4 only.
+pub fn write_proc_net_arp(entries: &[NeighborEntry]) -> String {
+    let mut out = format!("{}\n", PROC_NET_ARP_HEADER);
+    for entry in entries.iter().filter(|x| x.ip.is_ipv4()) {
+        let mac = entry.mac_lower().unwrap_or_else(|| "00:00:00:00:00:00".to_string());
+        let flags = format!("0x{:x}", entry.state.to_proc_flags());
+        let hw_type = format!("0x{:x}", ARPHRD_ETHER);
+        out.push_str(&format!("{:<16} {:<11} {:<11} {:<21} {:<8} {}\n", entry.ip, hw_type, flags, mac, "*", entry.device_or_dash()));
+    }
+    out
+}
+
+pub fn write_ip_neigh(entries: &[NeighborEntry]) -> String {
+    let mut out = String::new();
+    for entry in entries {
+        out.push_str(&format!("{} dev {}", entry.ip, entry.device_or_dash()));
+        if let Some(mac) = entry.mac_lower() {
+            out.push_str(&format!(" lladdr {}", mac));
+        }
+        out.push_str(&format!(" {}\n", entry.state));
+    }
+    out
+}
+
+/// Writes `arp -an` output. IPv6 entries are left out.
+pub fn write_arp_an(entries: &[NeighborEntry]) -> String {
+    let mut out = String::new();
+    for entry in entries.iter().filter(|x| x.ip.is_ipv4()) {
+        match entry.mac_lower() {
+            Some(mac) => {
+                let perm = if entry.state == NeighborState::Permanent { " PERM" } else { "" };
+                out.push_str(&format!("? ({}) at {} [ether]{} on {}\n", entry.ip, mac, perm, entry.device_or_dash()));
+            },
+            None => out.push_str(&format!("? ({}) at <incomplete> on {}\n", entry.ip, entry.device_or_dash())),
+        }
+    }
+    out
+}
+
+impl IpMacSet {
+    /// Adds neighbor table entries as if each had just been seen. Entries with a MAC are
+    /// learned as bindings and the rest as IP-only entries. Each entry's state is recorded on
+    /// every copy of it in the set, and PERMANENT entries never expire.
+    pub fn import_neighbors(&mut self, entries: &[NeighborEntry]) {
+        let now = Utc::now();
+        for entry in entries {
+            let combo = entry.to_combo();
+            match entry.mac {
+                Some(mac) => { self.learn_at(entry.ip, mac, now); },
+                None if !self.contains(&combo) => self.push_at(combo, now),
+                None => { self.touch_at(&combo, now); },
+            }
+            self.set_state(&combo, Some(entry.state));
+            if entry.state == NeighborState::Permanent {
+                self.set_ttl(&combo, None);
+            }
+        }
+    }
+
+    pub fn from_neighbors(entries: &[NeighborEntry]) -> IpMacSet {
+        let mut set = IpMacSet::new();
+        set.import_neighbors(entries);
+        set
+    }
+
+    /// Every entry with an IP, as neighbor table lines on `device`. Entries without a recorded
+    /// state are REACHABLE if they have a MAC and INCOMPLETE if not.
+    pub fn to_neighbors(&self, device: &str) -> Vec<NeighborEntry> {
+        self.iter_with_info()
+            .filter_map(|(combo, info)| combo.get_ip().map(|ip| {
+                let state = info.get_state().unwrap_or(if combo.has_mac() { NeighborState::Reachable } else { NeighborState::Incomplete });
+                NeighborEntry::new(ip, combo.get_mac(), Some(device), state)
+            }))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    const PROC_NET_ARP: &str = "IP address       HW type     Flags       HW address            Mask     Device
+192.168.1.1      0x1         0x2         00:11:22:33:44:55     *        eth0
+192.168.1.7      0x1         0x0         00:00:00:00:00:00     *        eth0
+192.168.1.9      0x1         0x6         00:11:22:33:44:99     *        eth0
+";
+
+    #[test]
+    fn round_trips_proc_net_arp() {
+        let entries = parse_proc_net_arp(PROC_NET_ARP).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].get_mac(), Some(MacAddress::from_bytes([0x00, 0x11, 0x22, 0x33, 0x44, 0x55])));
+        assert_eq!((entries[1].get_mac(), entries[1].get_state()), (None, NeighborState::Incomplete));
+        assert_eq!(entries[2].get_state(), NeighborState::Permanent);
+        assert_eq!(entries[2].get_device(), Some("eth0"));
+        assert_eq!(write_proc_net_arp(&entries), PROC_NET_ARP);
+
+        let err = parse_proc_net_arp("192.168.1.1      0x1         0x2").unwrap_err();
+        assert_eq!(err.kind, NeighborParseErrorKind::MissingField("HW address"));
+        assert_eq!(parse_proc_net_arp("192.168.1.1").unwrap_err().kind, NeighborParseErrorKind::MissingField("HW type"));
+        assert_eq!(parse_proc_net_arp("192.168.1.1 0x1 0x2 00:11:22:33:44:55 *").unwrap_err().kind, NeighborParseErrorKind::MissingField("Device"));
+        assert_eq!(parse_proc_net_arp("192.168.1.1 ether 0x2 00:11:22:33:44:55 * eth0").unwrap_err().kind, NeighborParseErrorKind::InvalidHwType);
+    }
+
+    #[test]
+    fn skips_other_hardware_types() {
+        let input = format!("{}192.168.2.5      0x20        0x2         80:00:02:08:fe:80:00:00:00:00:00:00:00:02:c9:03:00:1f:7b:b1     *        ib0\n", PROC_NET_ARP);
+        let entries = parse_proc_net_arp(&input).unwrap();
+        assert_eq!(entries, parse_proc_net_arp(PROC_NET_ARP).unwrap());
+    }
+
+    #[test]
+    fn round_trips_ip_neigh() {
+        let input = "192.168.1.1 dev eth0 lladdr 00:11:22:33:44:55 REACHABLE
+10.0.0.5 dev eth1 FAILED
+fe80::1 dev eth0 lladdr 00:11:22:33:44:55 router STALE
+";
+        let entries = parse_ip_neigh(input).unwrap();
+        assert_eq!(entries[1].get_state(), NeighborState::Failed);
+        assert_eq!(entries[2].get_ip(), "fe80::1".parse::<IpAddr>().unwrap());
+        assert_eq!(write_ip_neigh(&entries), input.replace(" router", ""));
+
+        let err = parse_ip_neigh("192.168.1.1 dev eth0 lladdr 00:11:22:33:44:55 REACHABLE\n192.168.1.2 dev eth0 lladdr zz BOGUS").unwrap_err();
+        assert_eq!((err.line, err.kind), (2, NeighborParseErrorKind::InvalidMac));
+        assert_eq!(parse_ip_neigh("192.168.1.2 dev eth0 BOGUS").unwrap_err().kind, NeighborParseErrorKind::UnknownState);
+
+        let failed = parse_ip_neigh("192.168.1.7 dev eth0 lladdr 00:11:22:33:44:77 FAILED\n192.168.1.8 dev eth0 lladdr 00:00:00:00:00:00 STALE").unwrap();
+        assert_eq!((failed[0].get_mac(), failed[1].get_mac()), (None, None));
+        let proc_failed = parse_proc_net_arp("192.168.1.7 0x1 0x0 00:11:22:33:44:77 * eth0").unwrap();
+        assert_eq!(IpMacSet::from_neighbors(&failed[..1]).iter().collect::<Vec<&IpMacCombo>>(), IpMacSet::from_neighbors(&proc_failed).iter().collect::<Vec<&IpMacCombo>>());
+    }
+
+    #[test]
+    fn round_trips_arp_an() {
+        let input = "? (192.168.1.1) at 00:11:22:33:44:55 [ether] on eth0
+? (192.168.1.7) at <incomplete> on eth0
+? (192.168.1.9) at 00:11:22:33:44:99 [ether] PERM on eth0
+";
+        let entries = parse_arp_an(input).unwrap();
+        assert_eq!(entries, parse_proc_net_arp(PROC_NET_ARP).unwrap());
+        assert_eq!(write_arp_an(&entries), input);
+        assert_eq!(parse_arp_an("? 192.168.1.1 at").unwrap_err().kind, NeighborParseErrorKind::MissingField("IP address"));
+    }
+
+    #[test]
+    fn imports_into_set() {
+        let set = IpMacSet::from_neighbors(&parse_proc_net_arp(PROC_NET_ARP).unwrap());
+        let gateway = IpMacCombo::new(Ipv4Addr::new(192, 168, 1, 1), MacAddress::from_bytes([0x00, 0x11, 0x22, 0x33, 0x44, 0x55]));
+        assert_eq!(set.get_info(&gateway).unwrap().get_state(), Some(NeighborState::Reachable));
+        assert_eq!(set.get_info(&IpMacCombo::from_ip(Ipv4Addr::new(192, 168, 1, 7))).unwrap().get_state(), Some(NeighborState::Incomplete));
+        assert_eq!(write_proc_net_arp(&set.to_neighbors("eth0")), PROC_NET_ARP);
+
+        let mut set = IpMacSet::new();
+        set.set_default_ttl(Some(chrono::Duration::seconds(60)));
+        set.push(gateway);
+        set.push(gateway);
+        set.import_neighbors(&[NeighborEntry::new(Ipv4Addr::new(192, 168, 1, 1), gateway.get_mac(), Some("eth0"), NeighborState::Permanent)]);
+        assert_eq!(set.get_indices(gateway), vec![0, 1]);
+        for (_, info) in set.iter_with_info() {
+            assert_eq!((info.get_state(), info.get_ttl()), (Some(NeighborState::Permanent), None));
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use std::fmt;
+
+use super::mac::{MacAddress,Eui64};
+
+/* src/layer2/ethernet/oui.rs
+ *
+ * Description: IEEE Registration Authority assignments, keyed by prefix. MA-L keys are the
+ * 24 bit OUI, MA-M keys the first 28 bits and MA-S keys the first 36 bits of the address.
+ * build.rs generates the maps from data/ieee/oui.csv, mam.csv and oui36.csv, which are kept in
+ * the format IEEE publishes; data/ieee/update.sh downloads fresh copies. Building without the
+ * "oui-registry" feature leaves this module out.
+ *
+ */
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OuiBlock {
+    MA_L,       // 24 bit prefix, formerly "OUI"
+    MA_M,       // 28 bit prefix
+    MA_S,       // 36 bit prefix, formerly "OUI-36" and "IAB"
+}
+
+impl OuiBlock {
+    pub fn prefix_len(&self) -> u8 {
+        match *self {
+            OuiBlock::MA_L => 24,
+            OuiBlock::MA_M => 28,
+            OuiBlock::MA_S => 36,
+        }
+    }
+}
+
+impl fmt::Display for OuiBlock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self,f)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vendor {
+    name: &'static str,
+    block: OuiBlock,
+}
+
+impl Vendor {
+    pub fn get_name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn get_block(&self) -> OuiBlock {
+        self.block
+    }
+}
+
+impl fmt::Display for Vendor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+// MA_L, MA_M and MA_S, generated by build.rs from data/ieee. phf divides by the map size on
+// lookup, so a listing with no rows has to be special-cased.
+include!(concat!(env!("OUT_DIR"), "/oui_registry.rs"));
+
+#[allow(non_snake_case)]
+pub fn MaLargeVendors(input: u32) -> Option<&'static &'static str> {
+    if MA_L.is_empty() { None } else { MA_L.get(&input) }
+}
+
+#[allow(non_snake_case)]
+pub fn MaMediumVendors(input: u32) -> Option<&'static &'static str> {
+    if MA_M.is_empty() { None } else { MA_M.get(&input) }
+}
+
+#[allow(non_snake_case)]
+pub fn MaSmallVendors(input: u64) -> Option<&'static &'static str> {
+    if MA_S.is_empty() { None } else { MA_S.get(&input) }
+}
+
+fn prefix_u64(bytes: &[u8]) -> u64 {
+    bytes[..5].iter().fold(0u64, |acc, x| (acc << 8) | *x as u64)
+}
+
+// Tries the longest assignment first, so an MA-S block carved out of a Registration Authority
+// MA-L resolves to the company that actually holds it.
+fn lookup(bytes: &[u8],
+          mal: fn(u32) -> Option<&'static &'static str>,
+          mam: fn(u32) -> Option<&'static &'static str>,
+          mas: fn(u64) -> Option<&'static &'static str>) -> Option<Vendor> {
+    let prefix = prefix_u64(bytes);
+    if let Some(name) = mas(prefix >> 4) {
+        return Some(Vendor { name, block: OuiBlock::MA_S });
+    }
+    if let Some(name) = mam((prefix >> 12) as u32) {
+        return Some(Vendor { name, block: OuiBlock::MA_M });
+    }
+    mal((prefix >> 16) as u32).map(|name| Vendor { name, block: OuiBlock::MA_L })
+}
+
+pub fn lookup_vendor(mac: &MacAddress) -> Option<Vendor> {
+    lookup(&mac.to_bytes(), MaLargeVendors, MaMediumVendors, MaSmallVendors)
+}
+
+impl MacAddress {
+    pub fn get_vendor(&self) -> Option<Vendor> {
+        lookup_vendor(self)
+    }
+}
+
+impl Eui64 {
+    pub fn get_vendor(&self) -> Option<Vendor> {
+        lookup(&self.to_bytes(), MaLargeVendors, MaMediumVendors, MaSmallVendors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Stand-ins for the generated MA-M and MA-S maps, one assignment each inside the
+    // Registration Authority's own 70:B3:D5 MA-L.
+    fn test_ma_m(input: u32) -> Option<&'static &'static str> {
+        if input == 0x70B3D51 { Some(&"Medium Holder") } else { None }
+    }
+
+    fn test_ma_s(input: u64) -> Option<&'static &'static str> {
+        if input == 0x70B3D5123 { Some(&"Small Holder") } else { None }
+    }
+
+    #[test]
+    fn looks_up_ma_l() {
+        let vendor = MacAddress::from_bytes([0x00, 0x50, 0x56, 0x01, 0x02, 0x03]).get_vendor().unwrap();
+        assert_eq!(vendor.get_name(), "VMware, Inc.");
+        assert_eq!(vendor.get_block(), OuiBlock::MA_L);
+        assert_eq!(Eui64::from_bytes([0x00, 0x1B, 0x21, 0xFF, 0xFE, 1, 2, 3]).get_vendor().unwrap().get_name(), "Intel Corporate");
+        assert_eq!(MacAddress::from_bytes([0x02, 0, 0, 0, 0, 1]).get_vendor(), None);
+    }
+
+    // Every assignment in the listings must come back out of lookup_vendor under its own
+    // block, which also checks that longer assignments win over the MA-L they sit in.
+    fn check_listing(listing: &str, block: OuiBlock) -> usize {
+        let mut checked = 0;
+        for line in listing.lines().skip(1).filter(|x| !x.trim().is_empty()) {
+            let assignment = line.split(',').nth(1).unwrap();
+            let prefix = u64::from_str_radix(assignment, 16).unwrap() << (48 - block.prefix_len());
+            let mac = MacAddress::from_u64(prefix | 0x7);
+            let vendor = lookup_vendor(&mac).unwrap();
+            assert_eq!(vendor.get_block(), block, "{}", line);
+            assert!(line.replace("\"\"", "\"").contains(vendor.get_name()), "{}", line);
+            checked += 1;
+        }
+        checked
+    }
+
+    #[test]
+    fn resolves_every_listed_assignment() {
+        assert!(check_listing(include_str!("../../../data/ieee/oui.csv"), OuiBlock::MA_L) > 0);
+        check_listing(include_str!("../../../data/ieee/mam.csv"), OuiBlock::MA_M);
+        check_listing(include_str!("../../../data/ieee/oui36.csv"), OuiBlock::MA_S);
+    }
+
+    // The checked-in listings are still partial: MA-M and MA-S have no rows yet. Once
+    // data/ieee/update.sh has been run and its output committed, drop the ignore.
+    #[test]
+    #[ignore = "data/ieee holds partial listings until update.sh output is committed"]
+    fn registries_are_complete() {
+        assert!(MA_L.len() > 30000);
+        assert!(!MA_M.is_empty());
+        assert!(!MA_S.is_empty());
+    }
+
+    #[test]
+    fn prefers_longest_prefix() {
+        let lookup_test = |bytes: [u8;6]| lookup(&bytes, MaLargeVendors, test_ma_m, test_ma_s).unwrap();
+        let vendor = lookup_test([0x70, 0xB3, 0xD5, 0x12, 0x3F, 0xFF]);
+        assert_eq!((vendor.get_name(), vendor.get_block().prefix_len()), ("Small Holder", 36));
+        let vendor = lookup_test([0x70, 0xB3, 0xD5, 0x14, 0x00, 0x00]);
+        assert_eq!((vendor.get_name(), vendor.get_block().prefix_len()), ("Medium Holder", 28));
+        let vendor = lookup_test([0x70, 0xB3, 0xD5, 0x20, 0x00, 0x00]);
+        assert_eq!((vendor.get_name(), vendor.get_block()), ("IEEE Registration Authority", OuiBlock::MA_L));
+    }
+}
@@ -0,0 +1,373 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use super::IpMacSet;
+use super::mac::{MacAddress,MacAddressParseError};
+
+const MAC_BITS: u8 = 48;
+const MAC_MAX: u64 = 0xFFFF_FFFF_FFFF;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MacPrefixError {
+    InvalidLength(u8),
+    InvalidAddress(MacAddressParseError),
+    Malformed(String),
+    EmptyRange,
+    // The prefix fixes the U/L bit to universal or the I/G bit to group.
+    NotLocalUnicast(MacPrefix),
+}
+
+impl Error for MacPrefixError {
+    fn description(&self) -> &str {
+        match *self {
+            MacPrefixError::InvalidLength(_) => "Prefix length is longer than 48 bits.",
+            MacPrefixError::InvalidAddress(_) => "Prefix address is not a valid MAC address.",
+            MacPrefixError::Malformed(_) => "Prefix is not in address/length form.",
+            MacPrefixError::EmptyRange => "Range ends before it starts.",
+            MacPrefixError::NotLocalUnicast(_) => "Prefix is not locally administered unicast.",
+        }
+    }
+}
+
+impl fmt::Display for MacPrefixError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MacPrefixError::InvalidLength(len) => write!(f,"MAC prefix length must be 0 to 48. Length: {}", len),
+            MacPrefixError::InvalidAddress(ref err) => write!(f,"{}", err),
+            MacPrefixError::Malformed(ref input) => write!(f,"MAC prefix must look like 02:00:5e:00:00:00/24. Input: {:?}", input),
+            MacPrefixError::EmptyRange => write!(f,"MAC range ends before it starts."),
+            MacPrefixError::NotLocalUnicast(prefix) => write!(f,"MAC prefix must have the U/L bit set and the I/G bit clear. Prefix: {}", prefix),
+        }
+    }
+}
+
+fn prefix_mask(len: u8) -> u64 {
+    if len == 0 { 0 } else { (MAC_MAX << (MAC_BITS - len)) & MAC_MAX }
+}
+
+/// A block of MAC addresses sharing the first `len` bits, written like 02:00:5E:00:00:00/24.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MacPrefix {
+    network: u64,
+    len: u8,
+}
+
+impl MacPrefix {
+    /// Bits of `addr` past `len` are cleared.
+    pub fn new(addr: MacAddress, len: u8) -> Result<MacPrefix, MacPrefixError> {
+        if len > MAC_BITS {
+            return Err(MacPrefixError::InvalidLength(len));
+        }
+        Ok(MacPrefix { network: addr.to_u64() & prefix_mask(len), len })
+    }
+
+    pub fn get_address(&self) -> MacAddress {
+        MacAddress::from_u64(self.network)
+    }
+
+    pub fn get_len(&self) -> u8 {
+        self.len
+    }
+
+    pub fn contains(&self, mac: &MacAddress) -> bool {
+        mac.to_u64() & prefix_mask(self.len) == self.network
+    }
+
+    pub fn contains_prefix(&self, other: &MacPrefix) -> bool {
+        other.len >= self.len && other.network & prefix_mask(self.len) == self.network
+    }
+
+    pub fn first(&self) -> MacAddress {
+        MacAddress::from_u64(self.network)
+    }
+
+    pub fn last(&self) -> MacAddress {
+        MacAddress::from_u64(self.network | !prefix_mask(self.len) & MAC_MAX)
+    }
+
+    pub fn count(&self) -> u64 {
+        1 << (MAC_BITS - self.len)
+    }
+
+    pub fn to_range(&self) -> MacRange {
+        MacRange { first: self.network, last: self.last().to_u64() }
+    }
+
+    pub fn iter(&self) -> MacRangeIter {
+        self.to_range().iter()
+    }
+
+    /// Every prefix of length `new_len` inside this one, in order. The pieces are produced as
+    /// they are iterated, so even a /0 split into /48s costs nothing up front.
+    pub fn split(&self, new_len: u8) -> Result<MacPrefixSplit, MacPrefixError> {
+        if new_len > MAC_BITS || new_len < self.len {
+            return Err(MacPrefixError::InvalidLength(new_len));
+        }
+        Ok(MacPrefixSplit { network: self.network, len: new_len, next: 0, count: 1 << (new_len - self.len) })
+    }
+
+    /// The lowest address in the prefix that no entry of `used` holds.
+    pub fn next_free(&self, used: &IpMacSet) -> Option<MacAddress> {
+        self.to_range().next_free(used)
+    }
+}
+
+impl FromStr for MacPrefix {
+    type Err = MacPrefixError;
+
+    fn from_str(s: &str) -> Result<MacPrefix, MacPrefixError> {
+        let mut parts = s.splitn(2, '/');
+        let addr = parts.next().unwrap_or("");
+        let len = parts.next().ok_or_else(|| MacPrefixError::Malformed(s.to_string()))?;
+        let len = len.parse::<u8>().map_err(|_| MacPrefixError::Malformed(s.to_string()))?;
+        MacPrefix::new(addr.parse().map_err(MacPrefixError::InvalidAddress)?, len)
+    }
+}
+
+impl fmt::Display for MacPrefix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"{}/{}", self.get_address(), self.len)
+    }
+}
+
+/// An inclusive run of MAC addresses that doesn't have to line up on a prefix boundary.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MacRange {
+    first: u64,
+    last: u64,
+}
+
+impl MacRange {
+    pub fn new(first: MacAddress, last: MacAddress) -> Result<MacRange, MacPrefixError> {
+        if first.to_u64() > last.to_u64() {
+            return Err(MacPrefixError::EmptyRange);
+        }
+        Ok(MacRange { first: first.to_u64(), last: last.to_u64() })
+    }
+
+    pub fn first(&self) -> MacAddress {
+        MacAddress::from_u64(self.first)
+    }
+
+    pub fn last(&self) -> MacAddress {
+        MacAddress::from_u64(self.last)
+    }
+
+    pub fn contains(&self, mac: &MacAddress) -> bool {
+        let value = mac.to_u64();
+        value >= self.first && value <= self.last
+    }
+
+    pub fn count(&self) -> u64 {
+        self.last - self.first + 1
+    }
+
+    pub fn iter(&self) -> MacRangeIter {
+        MacRangeIter { next: self.first, last: self.last, done: false }
+    }
+
+    /// Cuts the range into `parts` contiguous pieces whose sizes differ by at most one. Yields
+    /// fewer pieces if the range holds fewer than `parts` addresses.
+    pub fn split(&self, parts: u64) -> MacRangeSplit {
+        let parts = parts.clamp(1, self.count());
+        MacRangeSplit { first: self.first, size: self.count() / parts, extra: self.count() % parts, next: 0, parts }
+    }
+
+    /// The lowest address in the range that no entry of `used` holds.
+    pub fn next_free(&self, used: &IpMacSet) -> Option<MacAddress> {
+        self.iter().find(|mac| !used.contains_mac(*mac))
+    }
+}
+
+impl fmt::Display for MacRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"{}-{}", self.first(), self.last())
+    }
+}
+
+impl IntoIterator for &MacRange {
+    type Item = MacAddress;
+    type IntoIter = MacRangeIter;
+
+    fn into_iter(self) -> MacRangeIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for &MacPrefix {
+    type Item = MacAddress;
+    type IntoIter = MacRangeIter;
+
+    fn into_iter(self) -> MacRangeIter {
+        self.iter()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MacRangeIter {
+    next: u64,
+    last: u64,
+    done: bool,
+}
+
+impl Iterator for MacRangeIter {
+    type Item = MacAddress;
+
+    fn next(&mut self) -> Option<MacAddress> {
+        if self.done {
+            return None;
+        }
+        let mac = MacAddress::from_u64(self.next);
+        if self.next == self.last {
+            self.done = true;
+        } else {
+            self.next += 1;
+        }
+        Some(mac)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.done {
+            return (0, Some(0));
+        }
+        let remaining = self.last - self.next + 1;
+        if remaining > usize::MAX as u64 {
+            (usize::MAX, None)
+        } else {
+            (remaining as usize, Some(remaining as usize))
+        }
+    }
+}
+
+// A prefix holds at most 2^48 addresses, which only fits a 64 bit usize.
+#[cfg(target_pointer_width = "64")]
+impl ExactSizeIterator for MacRangeIter {}
+
+/// The pieces of `MacPrefix::split`, in order.
+#[derive(Clone, Debug)]
+pub struct MacPrefixSplit {
+    network: u64,
+    len: u8,
+    next: u64,
+    count: u64,
+}
+
+impl Iterator for MacPrefixSplit {
+    type Item = MacPrefix;
+
+    fn next(&mut self) -> Option<MacPrefix> {
+        if self.next == self.count {
+            return None;
+        }
+        let prefix = MacPrefix { network: self.network + (self.next << (MAC_BITS - self.len)), len: self.len };
+        self.next += 1;
+        Some(prefix)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.count - self.next;
+        if remaining > usize::MAX as u64 {
+            (usize::MAX, None)
+        } else {
+            (remaining as usize, Some(remaining as usize))
+        }
+    }
+}
+
+#[cfg(target_pointer_width = "64")]
+impl ExactSizeIterator for MacPrefixSplit {}
+
+/// The pieces of `MacRange::split`, in order.
+#[derive(Clone, Debug)]
+pub struct MacRangeSplit {
+    first: u64,
+    size: u64,
+    extra: u64,     // The first `extra` pieces are one address bigger
+    next: u64,
+    parts: u64,
+}
+
+impl Iterator for MacRangeSplit {
+    type Item = MacRange;
+
+    fn next(&mut self) -> Option<MacRange> {
+        if self.next == self.parts {
+            return None;
+        }
+        let len = if self.next < self.extra { self.size + 1 } else { self.size };
+        let range = MacRange { first: self.first, last: self.first + len - 1 };
+        self.first += len;
+        self.next += 1;
+        Some(range)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.parts - self.next;
+        if remaining > usize::MAX as u64 {
+            (usize::MAX, None)
+        } else {
+            (remaining as usize, Some(remaining as usize))
+        }
+    }
+}
+
+#[cfg(target_pointer_width = "64")]
+impl ExactSizeIterator for MacRangeSplit {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_contains() {
+        let prefix: MacPrefix = "02:00:5e:12:34:56/24".parse().unwrap();
+        assert_eq!(prefix.to_string(), "02:00:5E:00:00:00/24");
+        assert_eq!(prefix.count(), 1 << 24);
+        assert_eq!(prefix.last().to_string(), "02:00:5E:FF:FF:FF");
+        assert!(prefix.contains(&"02:00:5e:ab:cd:ef".parse().unwrap()));
+        assert!(!prefix.contains(&"02:00:5f:00:00:00".parse().unwrap()));
+
+        let prefix36: MacPrefix = "70:b3:d5:12:30:00/36".parse().unwrap();
+        assert_eq!(prefix36.count(), 4096);
+        assert!(!prefix.contains_prefix(&prefix36));
+        assert_eq!("02:00:5e:00:00:00/49".parse::<MacPrefix>(), Err(MacPrefixError::InvalidLength(49)));
+        assert!("02:00:5e:00:00:00".parse::<MacPrefix>().is_err());
+    }
+
+    #[test]
+    fn splits() {
+        let prefix: MacPrefix = "02:00:00:00:00:00/44".parse().unwrap();
+        let halves = prefix.split(45).unwrap().collect::<Vec<MacPrefix>>();
+        assert_eq!(halves.len(), 2);
+        assert_eq!(halves[1].first().to_string(), "02:00:00:00:00:08");
+        assert!(halves.iter().all(|x| prefix.contains_prefix(x)));
+
+        let ranges = prefix.to_range().split(3).collect::<Vec<MacRange>>();
+        assert_eq!(ranges.iter().map(|x| x.count()).collect::<Vec<u64>>(), vec![6, 5, 5]);
+        assert_eq!(ranges[2].last(), prefix.last());
+
+        // Nothing is materialised, however many pieces there are.
+        let everything = MacPrefix::new(MacAddress::from_u64(0), 0).unwrap();
+        let mut hosts = everything.split(48).unwrap();
+        assert_eq!(hosts.len(), 1 << 48);
+        assert_eq!(hosts.nth(5).unwrap().to_string(), "00:00:00:00:00:05/48");
+        assert_eq!(hosts.len(), (1 << 48) - 6);
+        assert_eq!(everything.to_range().split(1 << 40).len(), 1 << 40);
+        assert_eq!(everything.split(47).unwrap().nth(3).unwrap().to_string(), "00:00:00:00:00:06/47");
+        assert_eq!(everything.iter().len(), 1 << 48);
+    }
+
+    #[test]
+    fn iterates_and_allocates() {
+        let range = MacRange::new("02:00:00:00:00:fe".parse().unwrap(), "02:00:00:00:01:01".parse().unwrap()).unwrap();
+        let macs = range.iter().map(|x| x.to_string()).collect::<Vec<String>>();
+        assert_eq!(macs, vec!["02:00:00:00:00:FE", "02:00:00:00:00:FF", "02:00:00:00:01:00", "02:00:00:00:01:01"]);
+
+        let mut used = IpMacSet::new();
+        used.push_mac(range.first());
+        used.push_mac(MacAddress::from_u64(range.first().to_u64() + 1));
+        assert_eq!(range.next_free(&used).unwrap().to_string(), "02:00:00:00:01:00");
+        assert!(MacRange::new(range.last(), range.first()).is_err());
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher,Hasher};
+use std::time::{SystemTime,UNIX_EPOCH};
+
+use super::mac::MacAddress;
+use super::prefix::{MacPrefix,MacPrefixError};
+
+const UL_BIT: u64 = 0x0200_0000_0000;
+const IG_BIT: u64 = 0x0100_0000_0000;
+
+/// Deterministic source of unicast, locally administered MAC addresses. The same seed always
+/// yields the same sequence (SplitMix64), on every platform and release of this crate, so it
+/// is safe to use for generated fixtures.
+///
+/// When restricted to a prefix, the prefix bits are kept as given, so a prefix long enough to
+/// cover the U/L or I/G bit must already mark addresses as locally administered unicast.
+#[derive(Clone, Debug)]
+pub struct MacGenerator {
+    state: u64,
+    prefix: Option<MacPrefix>,
+}
+
+impl MacGenerator {
+    pub fn new(seed: u64) -> MacGenerator {
+        MacGenerator { state: seed, prefix: None }
+    }
+
+    /// Fails if the prefix fixes the U/L bit to universal (7 bits or more) or the I/G bit to
+    /// group (8 bits or more).
+    pub fn with_prefix(seed: u64, prefix: MacPrefix) -> Result<MacGenerator, MacPrefixError> {
+        let network = prefix.get_address().to_u64();
+        if (prefix.get_len() >= 7 && network & UL_BIT == 0) || (prefix.get_len() >= 8 && network & IG_BIT != 0) {
+            return Err(MacPrefixError::NotLocalUnicast(prefix));
+        }
+        Ok(MacGenerator { state: seed, prefix: Some(prefix) })
+    }
+
+    /// Addresses under a 24 bit prefix, such as a CID assigned by the IEEE. Company-assigned
+    /// OUIs have the U/L bit clear and are refused, since made-up addresses inside them could
+    /// collide with real hardware.
+    pub fn with_oui(seed: u64, oui: [u8;3]) -> Result<MacGenerator, MacPrefixError> {
+        let prefix = MacPrefix::new(MacAddress::from_bytes([oui[0], oui[1], oui[2], 0, 0, 0]), 24).unwrap();
+        MacGenerator::with_prefix(seed, prefix)
+    }
+
+    /// Seeded from the process's hash randomness and the clock. Not reproducible.
+    pub fn from_entropy() -> MacGenerator {
+        let mut hasher = RandomState::new().build_hasher();
+        if let Ok(now) = SystemTime::now().duration_since(UNIX_EPOCH) {
+            hasher.write_u128(now.as_nanos());
+        }
+        MacGenerator::new(hasher.finish())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    pub fn next_mac(&mut self) -> MacAddress {
+        let mut value = (self.next_u64() | UL_BIT) & !IG_BIT;
+        if let Some(prefix) = self.prefix {
+            let mask = 0xFFFF_FFFF_FFFFu64 >> prefix.get_len();
+            value = (value & mask) | prefix.get_address().to_u64();
+        }
+        MacAddress::from_u64(value)
+    }
+}
+
+impl Iterator for MacGenerator {
+    type Item = MacAddress;
+
+    fn next(&mut self) -> Option<MacAddress> {
+        Some(self.next_mac())
+    }
+}
+
+impl MacAddress {
+    /// A fresh unicast, locally administered address, e.g. for a veth or tap interface.
+    pub fn random_local() -> MacAddress {
+        MacGenerator::from_entropy().next_mac()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_reproducible() {
+        assert_eq!(MacGenerator::new(0).next_mac().to_string(), "AA:39:7B:1D:CD:AF");
+        let first = MacGenerator::new(42).take(16).collect::<Vec<MacAddress>>();
+        assert_eq!(first, MacGenerator::new(42).take(16).collect::<Vec<MacAddress>>());
+        assert!(first.iter().all(|x| x.is_local() && x.is_unicast()));
+        assert_ne!(first[0], first[1]);
+    }
+
+    #[test]
+    fn stays_inside_prefix() {
+        let prefix: MacPrefix = "0a:00:27:00:00:00/28".parse().unwrap();
+        assert!(MacGenerator::with_prefix(7, prefix).unwrap().take(64).all(|x| prefix.contains(&x)));
+        assert!(MacGenerator::with_oui(7, [0x0A, 0x50, 0x56]).unwrap().take(64).all(|x| x.get_oui() == [0x0A, 0x50, 0x56] && x.is_local()));
+
+        // Short prefixes leave the U/L and I/G bits to the generator.
+        let short: MacPrefix = "f0:00:00:00:00:00/4".parse().unwrap();
+        assert!(MacGenerator::with_prefix(7, short).unwrap().take(64).all(|x| short.contains(&x) && x.is_local() && x.is_unicast()));
+
+        let vmware = MacPrefix::new(MacAddress::from_bytes([0x00, 0x50, 0x56, 0, 0, 0]), 24).unwrap();
+        assert_eq!(MacGenerator::with_oui(7, [0x00, 0x50, 0x56]).unwrap_err(), MacPrefixError::NotLocalUnicast(vmware));
+        assert!(MacGenerator::with_prefix(7, "03:00:00:00:00:00/8".parse().unwrap()).is_err());
+        assert!(MacGenerator::with_prefix(7, "00:00:00:00:00:00/7".parse().unwrap()).is_err());
+        assert!(MacGenerator::with_prefix(7, "02:00:00:00:00:00/7".parse().unwrap()).unwrap().take(64).all(|x| x.is_local() && x.is_unicast()));
+
+        let mac = MacAddress::random_local();
+        assert!(mac.is_local() && mac.is_unicast());
+    }
+}
@@ -0,0 +1,603 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::net::IpAddr;
+
+use chrono::{DateTime,Duration,SecondsFormat,Utc};
+
+use super::ipmac::{EntryInfo,IpMacCombo,IpMacSet};
+use super::mac::{MacAddress,MacFormat};
+use super::neighbor::NeighborState;
+
+/* src/layer2/ethernet/snapshot.rs
+ *
+ * Description: Saves and loads IpMacSet contents as CSV or JSON. Both carry a format version;
+ * readers refuse versions newer than SNAPSHOT_VERSION. Entries are written sorted by IP and
+ * then MAC, so two snapshots of the same table are byte-for-byte identical.
+ *
+ * CSV:  # netdefs-ipmacset v1 taken 2017-08-01T12:00:00Z
+ *       ip,mac,first_seen,last_seen,ttl_secs,state
+ *       192.168.1.1,00:11:22:33:44:55,2017-08-01T11:00:00Z,2017-08-01T11:59:00Z,60,REACHABLE
+ *       192.168.1.7,,2017-08-01T11:00:00Z,2017-08-01T11:00:00Z,,
+ *
+ * JSON: {"format":"netdefs-ipmacset","version":1,"taken_at":"...","entries":[{"ip":"192.168.1.1",
+ *       "mac":"00:11:22:33:44:55","first_seen":"...","last_seen":"...","ttl_secs":60,"state":"REACHABLE"}]}
+ *
+ * Missing fields are empty in CSV and null in JSON.
+ *
+ */
+
+pub const SNAPSHOT_VERSION: u32 = 1;
+const SNAPSHOT_FORMAT: &str = "netdefs-ipmacset";
+const CSV_COLUMNS: &str = "ip,mac,first_seen,last_seen,ttl_secs,state";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SnapshotError {
+    MissingHeader,
+    UnsupportedVersion(u32),
+    InvalidCsv(usize, &'static str),    // Line, counting from 1, and what was wrong with it
+    InvalidJson(&'static str),
+}
+
+impl Error for SnapshotError {
+    fn description(&self) -> &str {
+        match *self {
+            SnapshotError::MissingHeader => "Snapshot has no format header.",
+            SnapshotError::UnsupportedVersion(_) => "Snapshot was written by a newer format version.",
+            SnapshotError::InvalidCsv(_, _) => "CSV snapshot is malformed.",
+            SnapshotError::InvalidJson(_) => "JSON snapshot is malformed.",
+        }
+    }
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SnapshotError::MissingHeader => write!(f,"Snapshot has no {} header.", SNAPSHOT_FORMAT),
+            SnapshotError::UnsupportedVersion(version) => write!(f,"Snapshot version {} is newer than supported version {}.", version, SNAPSHOT_VERSION),
+            SnapshotError::InvalidCsv(line, reason) => write!(f,"CSV snapshot line {}: {}", line, reason),
+            SnapshotError::InvalidJson(reason) => write!(f,"JSON snapshot: {}", reason),
+        }
+    }
+}
+
+/// An IpMacSet as it stood at `taken_at`.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    taken_at: DateTime<Utc>,
+    set: IpMacSet,
+}
+
+// One entry with its info, in output order.
+type Row = (IpMacCombo, EntryInfo);
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+fn parse_time(input: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(input).ok().map(|x| x.with_timezone(&Utc))
+}
+
+fn format_mac(mac: MacAddress) -> String {
+    mac.to_formatted_string(MacFormat::unix())
+}
+
+impl Snapshot {
+    pub fn new(set: IpMacSet) -> Snapshot {
+        Snapshot { taken_at: Utc::now(), set }
+    }
+
+    pub fn from_parts(set: IpMacSet, taken_at: DateTime<Utc>) -> Snapshot {
+        Snapshot { taken_at, set }
+    }
+
+    pub fn get_taken_at(&self) -> DateTime<Utc> {
+        self.taken_at
+    }
+
+    pub fn get_set(&self) -> &IpMacSet {
+        &self.set
+    }
+
+    pub fn into_set(self) -> IpMacSet {
+        self.set
+    }
+
+    fn rows(&self) -> Vec<Row> {
+        let mut rows = self.set.iter_with_info().map(|(combo, info)| (*combo, *info)).collect::<Vec<Row>>();
+        rows.sort_by_key(|x| (x.0.get_ip(), x.0.get_mac(), x.1.get_first_seen()));
+        rows
+    }
+
+    fn from_rows(rows: Vec<Row>, taken_at: DateTime<Utc>) -> Snapshot {
+        Snapshot { taken_at, set: rows.into_iter().collect() }
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = format!("# {} v{} taken {}\n{}\n", SNAPSHOT_FORMAT, SNAPSHOT_VERSION, format_time(self.taken_at), CSV_COLUMNS);
+        for (combo, info) in self.rows() {
+            out.push_str(&format!("{},{},{},{},{},{}\n",
+                combo.get_ip().map_or(String::new(), |x| x.to_string()),
+                combo.get_mac().map_or(String::new(), format_mac),
+                format_time(info.get_first_seen()),
+                format_time(info.get_last_seen()),
+                info.get_ttl().map_or(String::new(), |x| x.num_seconds().to_string()),
+                info.get_state().map_or("", |x| x.as_str())));
+        }
+        out
+    }
+
+    pub fn from_csv(input: &str) -> Result<Snapshot, SnapshotError> {
+        let mut lines = input.lines().enumerate().map(|(i, x)| (i + 1, x.trim())).filter(|x| !x.1.is_empty());
+        let header = lines.next().map(|x| x.1).ok_or(SnapshotError::MissingHeader)?;
+        let header = header.strip_prefix("# ").and_then(|x| x.strip_prefix(SNAPSHOT_FORMAT)).ok_or(SnapshotError::MissingHeader)?;
+        let fields = header.split_whitespace().collect::<Vec<&str>>();
+        if fields.len() != 3 || fields[1] != "taken" {
+            return Err(SnapshotError::InvalidCsv(1, "header must read \"# netdefs-ipmacset v<version> taken <time>\""));
+        }
+        let version = fields[0].strip_prefix('v').and_then(|x| x.parse::<u32>().ok()).filter(|x| *x >= 1).ok_or(SnapshotError::InvalidCsv(1, "bad version"))?;
+        if version > SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        let taken_at = parse_time(fields[2]).ok_or(SnapshotError::InvalidCsv(1, "bad timestamp"))?;
+
+        let mut rows = Vec::new();
+        for (line, content) in lines {
+            if content == CSV_COLUMNS {
+                continue;
+            }
+            let fields = content.split(',').collect::<Vec<&str>>();
+            if fields.len() != 6 {
+                return Err(SnapshotError::InvalidCsv(line, "expected 6 fields"));
+            }
+            let raw = RawRow {
+                ip: Some(fields[0]).filter(|x| !x.is_empty()),
+                mac: Some(fields[1]).filter(|x| !x.is_empty()),
+                first_seen: fields[2],
+                last_seen: fields[3],
+                ttl_secs: if fields[4].is_empty() { None } else { Some(fields[4].parse::<i64>().map_err(|_| SnapshotError::InvalidCsv(line, "bad ttl_secs"))?) },
+                state: Some(fields[5]).filter(|x| !x.is_empty()),
+            };
+            rows.push(raw.to_row().map_err(|x| SnapshotError::InvalidCsv(line, x))?);
+        }
+        Ok(Snapshot::from_rows(rows, taken_at))
+    }
+
+    pub fn to_json(&self) -> String {
+        let quote = |x: String| format!("\"{}\"", x);
+        let entries = self.rows().into_iter().map(|(combo, info)| {
+            format!("{{\"ip\":{},\"mac\":{},\"first_seen\":{},\"last_seen\":{},\"ttl_secs\":{},\"state\":{}}}",
+                combo.get_ip().map_or("null".to_string(), |x| quote(x.to_string())),
+                combo.get_mac().map_or("null".to_string(), |x| quote(format_mac(x))),
+                quote(format_time(info.get_first_seen())),
+                quote(format_time(info.get_last_seen())),
+                info.get_ttl().map_or("null".to_string(), |x| x.num_seconds().to_string()),
+                info.get_state().map_or("null".to_string(), |x| quote(x.as_str().to_string())))
+        }).collect::<Vec<String>>();
+        format!("{{\"format\":\"{}\",\"version\":{},\"taken_at\":\"{}\",\"entries\":[{}]}}\n",
+                SNAPSHOT_FORMAT, SNAPSHOT_VERSION, format_time(self.taken_at), entries.join(","))
+    }
+
+    pub fn from_json(input: &str) -> Result<Snapshot, SnapshotError> {
+        let root = json::parse(input).map_err(SnapshotError::InvalidJson)?;
+        let root = root.as_object().ok_or(SnapshotError::InvalidJson("top level must be an object"))?;
+        if root.get("format").and_then(|x| x.as_str()) != Some(SNAPSHOT_FORMAT) {
+            return Err(SnapshotError::MissingHeader);
+        }
+        let version = root.get("version").ok_or(SnapshotError::InvalidJson("missing version"))?;
+        let version = version.as_i64().filter(|x| *x >= 1).and_then(|x| u32::try_from(x).ok()).ok_or(SnapshotError::InvalidJson("bad version"))?;
+        if version > SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        let taken_at = root.get("taken_at").and_then(|x| x.as_str()).and_then(parse_time).ok_or(SnapshotError::InvalidJson("bad taken_at"))?;
+        let entries = root.get("entries").and_then(|x| x.as_array()).ok_or(SnapshotError::InvalidJson("missing entries"))?;
+
+        let mut rows = Vec::new();
+        for entry in entries {
+            let entry = entry.as_object().ok_or(SnapshotError::InvalidJson("entries must be objects"))?;
+            let text = |key: &str| -> Result<Option<&str>, SnapshotError> {
+                match entry.get(key) {
+                    None | Some(&json::Value::Null) => Ok(None),
+                    Some(value) => value.as_str().map(Some).ok_or(SnapshotError::InvalidJson("expected a string")),
+                }
+            };
+            let ttl_secs = match entry.get("ttl_secs") {
+                None | Some(&json::Value::Null) => None,
+                Some(value) => Some(value.as_i64().ok_or(SnapshotError::InvalidJson("bad ttl_secs"))?),
+            };
+            let raw = RawRow {
+                ip: text("ip")?,
+                mac: text("mac")?,
+                first_seen: text("first_seen")?.unwrap_or(""),
+                last_seen: text("last_seen")?.unwrap_or(""),
+                ttl_secs,
+                state: text("state")?,
+            };
+            rows.push(raw.to_row().map_err(SnapshotError::InvalidJson)?);
+        }
+        Ok(Snapshot::from_rows(rows, taken_at))
+    }
+}
+
+// Fields as read from either format, before validation.
+struct RawRow<'a> {
+    ip: Option<&'a str>,
+    mac: Option<&'a str>,
+    first_seen: &'a str,
+    last_seen: &'a str,
+    ttl_secs: Option<i64>,
+    state: Option<&'a str>,
+}
+
+impl<'a> RawRow<'a> {
+    fn to_row(&self) -> Result<Row, &'static str> {
+        let ip = match self.ip {
+            Some(ip) => Some(ip.parse::<IpAddr>().map_err(|_| "bad ip")?),
+            None => None,
+        };
+        let mac = match self.mac {
+            Some(mac) => Some(mac.parse::<MacAddress>().map_err(|_| "bad mac")?),
+            None => None,
+        };
+        let combo = match (ip, mac) {
+            (Some(ip), Some(mac)) => IpMacCombo::new(ip, mac),
+            (Some(ip), None) => IpMacCombo::from_ip(ip),
+            (None, Some(mac)) => IpMacCombo::from_mac(mac),
+            (None, None) => return Err("entry has neither ip nor mac"),
+        };
+        let first_seen = parse_time(self.first_seen).ok_or("bad first_seen")?;
+        let last_seen = parse_time(self.last_seen).ok_or("bad last_seen")?;
+        let state = match self.state {
+            Some(state) => Some(state.parse::<NeighborState>().map_err(|_| "bad state")?),
+            None => None,
+        };
+        let ttl = match self.ttl_secs {
+            Some(secs) if secs < 0 => return Err("negative ttl_secs"),
+            Some(secs) => Some(Duration::try_seconds(secs).ok_or("bad ttl_secs")?),
+            None => None,
+        };
+        Ok((combo, EntryInfo::from_parts(first_seen, last_seen, ttl, state)))
+    }
+}
+
+// Just enough JSON to read snapshots back. Nesting is capped at MAX_DEPTH so crafted input
+// can't exhaust the stack.
+mod json {
+    use super::BTreeMap;
+
+    const MAX_DEPTH: usize = 64;
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Value {
+        Null,
+        Bool(bool),
+        Number(f64),
+        String(String),
+        Array(Vec<Value>),
+        Object(BTreeMap<String, Value>),
+    }
+
+    impl Value {
+        pub fn as_str(&self) -> Option<&str> {
+            match *self { Value::String(ref x) => Some(x), _ => None }
+        }
+
+        pub fn as_i64(&self) -> Option<i64> {
+            // -(i64::MIN) is 2^63, the first value past i64::MAX, and is exact as an f64.
+            match *self { Value::Number(x) if x.fract() == 0.0 && x >= i64::MIN as f64 && x < -(i64::MIN as f64) => Some(x as i64), _ => None }
+        }
+
+        pub fn as_array(&self) -> Option<&Vec<Value>> {
+            match *self { Value::Array(ref x) => Some(x), _ => None }
+        }
+
+        pub fn as_object(&self) -> Option<&BTreeMap<String, Value>> {
+            match *self { Value::Object(ref x) => Some(x), _ => None }
+        }
+    }
+
+    struct Parser<'a> {
+        bytes: &'a [u8],
+        pos: usize,
+        depth: usize,
+    }
+
+    pub fn parse(input: &str) -> Result<Value, &'static str> {
+        let mut parser = Parser { bytes: input.as_bytes(), pos: 0, depth: 0 };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos != parser.bytes.len() {
+            return Err("trailing characters");
+        }
+        Ok(value)
+    }
+
+    impl<'a> Parser<'a> {
+        fn skip_whitespace(&mut self) {
+            while self.pos < self.bytes.len() && (self.bytes[self.pos] as char).is_ascii_whitespace() {
+                self.pos += 1;
+            }
+        }
+
+        fn peek(&mut self) -> Option<u8> {
+            self.skip_whitespace();
+            self.bytes.get(self.pos).cloned()
+        }
+
+        fn expect(&mut self, byte: u8) -> Result<(), &'static str> {
+            if self.peek() != Some(byte) {
+                return Err("unexpected character");
+            }
+            self.pos += 1;
+            Ok(())
+        }
+
+        fn literal(&mut self, word: &str, value: Value) -> Result<Value, &'static str> {
+            if !self.bytes[self.pos..].starts_with(word.as_bytes()) {
+                return Err("unexpected character");
+            }
+            self.pos += word.len();
+            Ok(value)
+        }
+
+        fn value(&mut self) -> Result<Value, &'static str> {
+            match self.peek() {
+                Some(b'{') => self.nested(Parser::object),
+                Some(b'[') => self.nested(Parser::array),
+                Some(b'"') => self.string().map(Value::String),
+                Some(b'n') => self.literal("null", Value::Null),
+                Some(b't') => self.literal("true", Value::Bool(true)),
+                Some(b'f') => self.literal("false", Value::Bool(false)),
+                Some(b'-') | Some(b'0'..=b'9') => self.number(),
+                Some(_) => Err("unexpected character"),
+                None => Err("unexpected end of input"),
+            }
+        }
+
+        fn nested(&mut self, parse: fn(&mut Parser<'a>) -> Result<Value, &'static str>) -> Result<Value, &'static str> {
+            if self.depth == MAX_DEPTH {
+                return Err("nested too deeply");
+            }
+            self.depth += 1;
+            let value = parse(self);
+            self.depth -= 1;
+            value
+        }
+
+        fn object(&mut self) -> Result<Value, &'static str> {
+            self.expect(b'{')?;
+            let mut map = BTreeMap::new();
+            if self.peek() == Some(b'}') {
+                self.pos += 1;
+                return Ok(Value::Object(map));
+            }
+            loop {
+                if self.peek() != Some(b'"') {
+                    return Err("object keys must be strings");
+                }
+                let key = self.string()?;
+                self.expect(b':')?;
+                map.insert(key, self.value()?);
+                match self.peek() {
+                    Some(b',') => self.pos += 1,
+                    Some(b'}') => { self.pos += 1; return Ok(Value::Object(map)); },
+                    _ => return Err("expected , or }"),
+                }
+            }
+        }
+
+        fn array(&mut self) -> Result<Value, &'static str> {
+            self.expect(b'[')?;
+            let mut items = Vec::new();
+            if self.peek() == Some(b']') {
+                self.pos += 1;
+                return Ok(Value::Array(items));
+            }
+            loop {
+                items.push(self.value()?);
+                match self.peek() {
+                    Some(b',') => self.pos += 1,
+                    Some(b']') => { self.pos += 1; return Ok(Value::Array(items)); },
+                    _ => return Err("expected , or ]"),
+                }
+            }
+        }
+
+        fn string(&mut self) -> Result<String, &'static str> {
+            self.expect(b'"')?;
+            let mut out = Vec::new();
+            loop {
+                let byte = *self.bytes.get(self.pos).ok_or("unterminated string")?;
+                self.pos += 1;
+                match byte {
+                    b'"' => return String::from_utf8(out).map_err(|_| "invalid UTF-8"),
+                    b'\\' => {
+                        let escaped = *self.bytes.get(self.pos).ok_or("unterminated string")?;
+                        self.pos += 1;
+                        match escaped {
+                            b'"' | b'\\' | b'/' => out.push(escaped),
+                            b'n' => out.push(b'\n'),
+                            b't' => out.push(b'\t'),
+                            b'r' => out.push(b'\r'),
+                            b'b' => out.push(0x08),
+                            b'f' => out.push(0x0C),
+                            b'u' => {
+                                let c = self.unicode_escape()?;
+                                out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                            },
+                            _ => return Err("unsupported escape"),
+                        }
+                    },
+                    0x00..=0x1F => return Err("control character in string"),
+                    _ => out.push(byte),
+                }
+            }
+        }
+
+        fn hex4(&mut self) -> Result<u32, &'static str> {
+            let digits = self.bytes.get(self.pos..self.pos + 4).ok_or("unterminated string")?;
+            // from_str_radix would also take a leading sign.
+            if !digits.iter().all(|x| x.is_ascii_hexdigit()) {
+                return Err("bad \\u escape");
+            }
+            let value = u32::from_str_radix(::std::str::from_utf8(digits).unwrap(), 16).unwrap();
+            self.pos += 4;
+            Ok(value)
+        }
+
+        // The four hex digits after \u, plus the low half that must follow a high surrogate.
+        fn unicode_escape(&mut self) -> Result<char, &'static str> {
+            let high = self.hex4()?;
+            let code = match high {
+                0xD800..=0xDBFF => {
+                    if !self.bytes[self.pos..].starts_with(b"\\u") {
+                        return Err("unpaired surrogate");
+                    }
+                    self.pos += 2;
+                    let low = self.hex4()?;
+                    if !(0xDC00..=0xDFFF).contains(&low) {
+                        return Err("unpaired surrogate");
+                    }
+                    0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                },
+                0xDC00..=0xDFFF => return Err("unpaired surrogate"),
+                _ => high,
+            };
+            ::std::char::from_u32(code).ok_or("bad \\u escape")
+        }
+
+        fn digits(&mut self) -> usize {
+            let start = self.pos;
+            while self.bytes.get(self.pos).is_some_and(|x| x.is_ascii_digit()) {
+                self.pos += 1;
+            }
+            self.pos - start
+        }
+
+        // -?(0|[1-9][0-9]*)(.[0-9]+)?([eE][+-]?[0-9]+)?, as RFC 8259 has it.
+        fn number(&mut self) -> Result<Value, &'static str> {
+            let start = self.pos;
+            if self.bytes.get(self.pos) == Some(&b'-') {
+                self.pos += 1;
+            }
+            let leading_zero = self.bytes.get(self.pos) == Some(&b'0');
+            match self.digits() {
+                0 => return Err("bad number"),
+                1 => (),
+                _ if leading_zero => return Err("bad number"),
+                _ => (),
+            }
+            if self.bytes.get(self.pos) == Some(&b'.') {
+                self.pos += 1;
+                if self.digits() == 0 {
+                    return Err("bad number");
+                }
+            }
+            if matches!(self.bytes.get(self.pos), Some(b'e') | Some(b'E')) {
+                self.pos += 1;
+                if matches!(self.bytes.get(self.pos), Some(b'+') | Some(b'-')) {
+                    self.pos += 1;
+                }
+                if self.digits() == 0 {
+                    return Err("bad number");
+                }
+            }
+            let text = ::std::str::from_utf8(&self.bytes[start..self.pos]).map_err(|_| "bad number")?;
+            text.parse::<f64>().map(Value::Number).map_err(|_| "bad number")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    fn sample() -> Snapshot {
+        let start = parse_time("2017-08-01T11:00:00Z").unwrap();
+        let mut set = IpMacSet::with_default_ttl(Duration::seconds(60));
+        set.learn_at(Ipv4Addr::new(192, 168, 1, 1), "00:11:22:33:44:55".parse().unwrap(), start);
+        set.push_at(IpMacCombo::from_ip(Ipv4Addr::new(192, 168, 1, 7)), start);
+        set.push_at(IpMacCombo::from_mac("02:00:00:00:00:01".parse().unwrap()), start + Duration::milliseconds(250));
+        set.learn_at("fe80::1".parse::<IpAddr>().unwrap(), "00:11:22:33:44:55".parse().unwrap(), start);
+        let gateway = IpMacCombo::new(Ipv4Addr::new(192, 168, 1, 1), "00:11:22:33:44:55".parse().unwrap());
+        set.set_state(&gateway, Some(NeighborState::Reachable));
+        set.touch_at(&gateway, start + Duration::seconds(59));
+        Snapshot::from_parts(set, parse_time("2017-08-01T12:00:00Z").unwrap())
+    }
+
+    #[test]
+    fn round_trips_csv() {
+        let csv = sample().to_csv();
+        assert_eq!(csv.lines().take(4).collect::<Vec<&str>>(), vec![
+            "# netdefs-ipmacset v1 taken 2017-08-01T12:00:00Z",
+            CSV_COLUMNS,
+            ",02:00:00:00:00:01,2017-08-01T11:00:00.250Z,2017-08-01T11:00:00.250Z,60,",
+            "192.168.1.1,00:11:22:33:44:55,2017-08-01T11:00:00Z,2017-08-01T11:00:59Z,60,REACHABLE",
+        ]);
+        let loaded = Snapshot::from_csv(&csv).unwrap();
+        assert_eq!(loaded.get_taken_at(), sample().get_taken_at());
+        assert_eq!(loaded.to_csv(), csv);
+        assert!(loaded.get_set().contains(&IpMacCombo::from_ip(Ipv4Addr::new(192, 168, 1, 7))));
+
+        let bad = csv.replace("192.168.1.7", "192.168.1.300");
+        assert_eq!(Snapshot::from_csv(&bad).unwrap_err(), SnapshotError::InvalidCsv(5, "bad ip"));
+        let negative = csv.replacen(",60,", ",-60,", 1);
+        assert_eq!(Snapshot::from_csv(&negative).unwrap_err(), SnapshotError::InvalidCsv(3, "negative ttl_secs"));
+        let huge = csv.replacen(",60,", ",9223372036854775807,", 1);
+        assert_eq!(Snapshot::from_csv(&huge).unwrap_err(), SnapshotError::InvalidCsv(3, "bad ttl_secs"));
+        assert_eq!(Snapshot::from_csv(&csv.replace(" v1 ", " v2 ")).unwrap_err(), SnapshotError::UnsupportedVersion(2));
+        assert_eq!(Snapshot::from_csv(&csv.replace(" v1 ", " v0 ")).unwrap_err(), SnapshotError::InvalidCsv(1, "bad version"));
+        assert_eq!(Snapshot::from_csv("ip,mac\n").unwrap_err(), SnapshotError::MissingHeader);
+    }
+
+    #[test]
+    fn round_trips_json() {
+        let json = sample().to_json();
+        assert!(json.starts_with("{\"format\":\"netdefs-ipmacset\",\"version\":1,\"taken_at\":\"2017-08-01T12:00:00Z\",\"entries\":[{\"ip\":null,"));
+        let loaded = Snapshot::from_json(&json).unwrap();
+        assert_eq!(loaded.to_json(), json);
+        assert_eq!(loaded.to_csv(), sample().to_csv());
+        assert_eq!(Snapshot::from_json(&json.replace("\"version\":1", "\"version\":7")).unwrap_err(), SnapshotError::UnsupportedVersion(7));
+        assert!(Snapshot::from_json(&json[..json.len() - 3]).is_err());
+        for version in ["0", "-1", "-4294967295", "4294967297", "1.5"].iter() {
+            let bad = json.replace("\"version\":1", &format!("\"version\":{}", version));
+            assert_eq!(Snapshot::from_json(&bad).unwrap_err(), SnapshotError::InvalidJson("bad version"));
+        }
+        assert!(Snapshot::from_json(&json.replace("\"version\":1", "\"version\":1e0")).is_ok());
+
+        let negative = json.replacen("\"ttl_secs\":60", "\"ttl_secs\":-60", 1);
+        assert_eq!(Snapshot::from_json(&negative).unwrap_err(), SnapshotError::InvalidJson("negative ttl_secs"));
+        let padded = json.replacen("\"ttl_secs\":60", "\"ttl_secs\":060", 1);
+        assert_eq!(Snapshot::from_json(&padded).unwrap_err(), SnapshotError::InvalidJson("bad number"));
+    }
+
+    #[test]
+    fn reads_any_valid_json() {
+        let value = json::parse("{\"a\":[1e3,-2.5E-1,0],\"b\":\"caf\\u00e9 \\ud83d\\ude00\\b\"}").unwrap();
+        let object = value.as_object().unwrap();
+        assert_eq!(object["a"], json::Value::Array(vec![json::Value::Number(1000.0), json::Value::Number(-0.25), json::Value::Number(0.0)]));
+        assert_eq!(object["b"].as_str(), Some("caf\u{e9} \u{1F600}\u{8}"));
+
+        assert_eq!(json::parse("\"\\ud83d\""), Err("unpaired surrogate"));
+        assert_eq!(json::parse("\"\\ude00\""), Err("unpaired surrogate"));
+        assert_eq!(json::parse("\"\\u00g1\""), Err("bad \\u escape"));
+        assert_eq!(json::parse("\"\\u+123\""), Err("bad \\u escape"));
+        assert_eq!(json::parse("\"\\u-123\""), Err("bad \\u escape"));
+        for bad in ["01", "-01", "00", "1.", "-", "1e", "1e+", "1.e3"].iter() {
+            assert_eq!(json::parse(bad), Err("bad number"), "{}", bad);
+        }
+        assert!(json::parse(".5").is_err() && json::parse("+1").is_err());
+        assert_eq!(json::parse("-0.5e+2"), Ok(json::Value::Number(-50.0)));
+        assert_eq!(json::parse("0"), Ok(json::Value::Number(0.0)));
+        assert_eq!(json::parse("[10,0]"), Ok(json::Value::Array(vec![json::Value::Number(10.0), json::Value::Number(0.0)])));
+        assert_eq!(json::parse("\"tab\there\""), Err("control character in string"));
+
+        let deep = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+        assert_eq!(json::parse(&deep), Err("nested too deeply"));
+        let ok = format!("{}{}", "[".repeat(64), "]".repeat(64));
+        assert!(json::parse(&ok).is_ok());
+    }
+}
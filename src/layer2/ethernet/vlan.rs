@@ -0,0 +1,242 @@
+use std::slice;
+
+pub const TPID_CUSTOMER: u16 = 0x8100;      // 802.1Q C-tag
+pub const TPID_SERVICE: u16 = 0x88A8;       // 802.1ad S-tag
+pub const TPID_LEGACY_QINQ: u16 = 0x9100;   // Pre-802.1ad vendor QinQ
+
+pub const VLAN_TAG_LEN: usize = 4;
+
+pub fn is_vlan_tpid(etype: u16) -> bool {
+    etype == TPID_CUSTOMER || etype == TPID_SERVICE || etype == TPID_LEGACY_QINQ
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VLAN_Tag {
+    proto_id: u16,
+    ctl_info: u16,
+}
+
+impl VLAN_Tag {
+    pub fn new(etype: u16, ctl: u16) -> VLAN_Tag {
+        VLAN_Tag { proto_id: etype, ctl_info: ctl }
+    }
+
+    // PCP is masked to 3 bits and the VLAN ID to 12.
+    pub fn from_fields(etype: u16, pcp: u8, dei: bool, vlan_id: u16) -> VLAN_Tag {
+        let ctl = ((pcp as u16 & 0x7) << 13) | if dei { 0x1000 } else { 0 } | (vlan_id & 0x0FFF);
+        VLAN_Tag { proto_id: etype, ctl_info: ctl }
+    }
+
+    pub fn from_bytes(bytes: [u8;4]) -> VLAN_Tag {
+        let proto = ((bytes[0] as u16) << 8) + (bytes[1] as u16);
+        let ctl = ((bytes[2] as u16) << 8) + (bytes[3] as u16);
+        VLAN_Tag { proto_id: proto, ctl_info: ctl }
+    }
+
+    pub fn to_bytes(&self) -> [u8;4] {
+        [(self.proto_id >> 8) as u8, self.proto_id as u8, (self.ctl_info >> 8) as u8, self.ctl_info as u8]
+    }
+
+    pub fn get_proto_id(&self) -> u16 {
+        self.proto_id
+    }
+
+    pub fn get_ctl_info(&self) -> u16 {
+        self.ctl_info
+    }
+
+    // Technically 3 bits, but smallest is bool or u8.
+    pub fn get_pcp(&self) -> u8 {
+        (self.ctl_info >> 13) as u8
+    }
+
+    pub fn get_dei(&self) -> bool {
+        (self.ctl_info & 0x1000) == 0x1000
+    }
+
+    pub fn get_vlan_id(&self) -> u16 {
+        self.ctl_info & 0x0FFF
+    }
+
+    pub fn set_proto_id(&mut self, etype: u16) {
+        self.proto_id = etype;
+    }
+
+    pub fn set_pcp(&mut self, pcp: u8) {
+        self.ctl_info = (self.ctl_info & 0x1FFF) | ((pcp as u16 & 0x7) << 13);
+    }
+
+    pub fn set_dei(&mut self, dei: bool) {
+        self.ctl_info = (self.ctl_info & 0xEFFF) | if dei { 0x1000 } else { 0 };
+    }
+
+    pub fn set_vlan_id(&mut self, vlan_id: u16) {
+        self.ctl_info = (self.ctl_info & 0xF000) | (vlan_id & 0x0FFF);
+    }
+}
+
+/// An ordered stack of VLAN tags. Index 0 is the outermost tag, the one closest to the MAC addresses.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VlanStack {
+    tags: Vec<VLAN_Tag>,
+}
+
+impl VlanStack {
+    pub fn new() -> VlanStack {
+        VlanStack { tags: Vec::new() }
+    }
+
+    /// Tags are given outermost first.
+    pub fn from_tags(tags: Vec<VLAN_Tag>) -> VlanStack {
+        VlanStack { tags }
+    }
+
+    /// Reads consecutive tags from the start of `bytes` for as long as they carry a VLAN TPID.
+    /// Returns the stack and the number of bytes it took up. A tag cut off by the end of the
+    /// buffer is left unread.
+    pub fn parse(bytes: &[u8]) -> (VlanStack, usize) {
+        let mut tags = Vec::new();
+        let mut offset = 0;
+        while bytes.len() >= offset + VLAN_TAG_LEN && is_vlan_tpid(((bytes[offset] as u16) << 8) + (bytes[offset + 1] as u16)) {
+            tags.push(VLAN_Tag::from_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]]));
+            offset += VLAN_TAG_LEN;
+        }
+        (VlanStack { tags }, offset)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.tags.iter().flat_map(|x| x.to_bytes().to_vec()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tags.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    /// Length of the stack on the wire.
+    pub fn byte_len(&self) -> usize {
+        self.tags.len() * VLAN_TAG_LEN
+    }
+
+    pub fn get(&self, index: usize) -> Option<&VLAN_Tag> {
+        self.tags.get(index)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut VLAN_Tag> {
+        self.tags.get_mut(index)
+    }
+
+    pub fn outer(&self) -> Option<&VLAN_Tag> {
+        self.tags.first()
+    }
+
+    pub fn inner(&self) -> Option<&VLAN_Tag> {
+        self.tags.last()
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, VLAN_Tag> {
+        self.tags.iter()
+    }
+
+    /// Adds a new outermost tag.
+    pub fn push(&mut self, tag: VLAN_Tag) {
+        self.tags.insert(0, tag);
+    }
+
+    /// Adds a new innermost tag, directly before the EtherType.
+    pub fn push_inner(&mut self, tag: VLAN_Tag) {
+        self.tags.push(tag);
+    }
+
+    /// Removes the outermost tag.
+    pub fn pop(&mut self) -> Option<VLAN_Tag> {
+        if self.tags.is_empty() {
+            None
+        }
+        else {
+            Some(self.tags.remove(0))
+        }
+    }
+
+    pub fn pop_inner(&mut self) -> Option<VLAN_Tag> {
+        self.tags.pop()
+    }
+
+    /// Replaces the tag at `index`, returning the old one. Returns None and changes nothing if
+    /// there is no tag at that depth.
+    pub fn rewrite(&mut self, index: usize, tag: VLAN_Tag) -> Option<VLAN_Tag> {
+        self.tags.get_mut(index).map(|x| {
+            let old = *x;
+            *x = tag;
+            old
+        })
+    }
+
+    /// Changes only the VLAN ID of the tag at `index`, keeping its TPID, PCP and DEI.
+    pub fn rewrite_vlan_id(&mut self, index: usize, vlan_id: u16) -> bool {
+        match self.tags.get_mut(index) {
+            Some(tag) => {
+                tag.set_vlan_id(vlan_id);
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a VlanStack {
+    type Item = &'a VLAN_Tag;
+    type IntoIter = slice::Iter<'a, VLAN_Tag>;
+
+    fn into_iter(self) -> slice::Iter<'a, VLAN_Tag> {
+        self.tags.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tag_fields() {
+        let tag = VLAN_Tag::from_bytes([0x81, 0x00, 0xB0, 0x64]);
+        assert_eq!(tag.get_proto_id(), TPID_CUSTOMER);
+        assert_eq!(tag.get_pcp(), 5);
+        assert!(tag.get_dei());
+        assert_eq!(tag.get_vlan_id(), 100);
+        assert_eq!(tag, VLAN_Tag::from_fields(TPID_CUSTOMER, 5, true, 100));
+
+        let mut tag = tag;
+        tag.set_vlan_id(4095);
+        tag.set_pcp(0);
+        tag.set_dei(false);
+        assert_eq!(tag.to_bytes(), [0x81, 0x00, 0x0F, 0xFF]);
+    }
+
+    #[test]
+    fn parses_triple_tagged_stack() {
+        let bytes = [0x91, 0x00, 0x00, 0x01, 0x88, 0xA8, 0x00, 0x02, 0x81, 0x00, 0x00, 0x03, 0x08, 0x00];
+        let (stack, len) = VlanStack::parse(&bytes);
+        assert_eq!(len, 12);
+        assert_eq!(stack.iter().map(|x| x.get_vlan_id()).collect::<Vec<u16>>(), vec![1, 2, 3]);
+        assert_eq!(stack.to_bytes(), bytes[..12].to_vec());
+    }
+
+    #[test]
+    fn push_pop_rewrite() {
+        let mut stack = VlanStack::from_tags(vec![VLAN_Tag::from_fields(TPID_CUSTOMER, 0, false, 10)]);
+        stack.push(VLAN_Tag::from_fields(TPID_SERVICE, 0, false, 200));
+        assert_eq!(stack.outer().unwrap().get_vlan_id(), 200);
+        assert_eq!(stack.inner().unwrap().get_vlan_id(), 10);
+
+        assert!(stack.rewrite_vlan_id(1, 20));
+        assert!(!stack.rewrite_vlan_id(2, 20));
+        assert_eq!(stack.pop().unwrap().get_vlan_id(), 200);
+        assert_eq!(stack.pop().unwrap().get_vlan_id(), 20);
+        assert!(stack.pop().is_none());
+    }
+}
@@ -0,0 +1,321 @@
+use std::error::Error;
+use std::fmt;
+
+use layer3::NetworkProtocol;
+use super::ethernet::{FCS,FrameView,MacAddress};
+
+const FCOE_HEADER_LEN: usize = 14;     // Version, reserved bits and SOF.
+const FCOE_TRAILER_LEN: usize = 8;     // FC CRC, EOF and reserved bits.
+const FC_HEADER_LEN: usize = 24;
+
+// Default FC-MAP, the upper half of fabric provided MAC addresses.
+pub const DEFAULT_FC_MAP: [u8;3] = [0x0E, 0xFC, 0x00];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FcoeError {
+    Truncated { needed: usize, actual: usize },
+    NotFcoe(u16),
+}
+
+impl Error for FcoeError {
+    fn description(&self) -> &str {
+        match *self {
+            FcoeError::Truncated { .. } => "FCoE frame is truncated.",
+            FcoeError::NotFcoe(_) => "Frame does not carry FCoE.",
+        }
+    }
+}
+
+impl fmt::Display for FcoeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FcoeError::Truncated { needed, actual } => write!(f,"FCoE frame is truncated. Needed {} bytes, got {}", needed, actual),
+            FcoeError::NotFcoe(val) => write!(f,"Frame does not carry FCoE. EtherType: {:04X}", val),
+        }
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StartOfFrame {
+    SOFf,
+    SOFi2,
+    SOFn2,
+    SOFi3,
+    SOFn3,
+    SOFi4,
+    SOFn4,
+    SOFc4,
+}
+
+impl fmt::Display for StartOfFrame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self,f)
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EndOfFrame {
+    EOFn,
+    EOFt,
+    EOFrt,
+    EOFdt,
+    EOFni,
+    EOFdti,
+    EOFrti,
+    EOFa,
+}
+
+impl fmt::Display for EndOfFrame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self,f)
+    }
+}
+
+// Defined in RFC 3643, "Fibre Channel (FC) Frame Encapsulation".
+u8_key_phf_map! {
+    StartOfFrameDelimiters -> StartOfFrame = {
+        0x28 => StartOfFrame::SOFf,
+        0x2D => StartOfFrame::SOFi2,
+        0x35 => StartOfFrame::SOFn2,
+        0x2E => StartOfFrame::SOFi3,
+        0x36 => StartOfFrame::SOFn3,
+        0x29 => StartOfFrame::SOFi4,
+        0x31 => StartOfFrame::SOFn4,
+        0x39 => StartOfFrame::SOFc4,
+    }
+}
+
+u8_key_phf_map! {
+    EndOfFrameDelimiters -> EndOfFrame = {
+        0x41 => EndOfFrame::EOFn,
+        0x42 => EndOfFrame::EOFt,
+        0x44 => EndOfFrame::EOFrt,
+        0x46 => EndOfFrame::EOFdt,
+        0x49 => EndOfFrame::EOFni,
+        0x4E => EndOfFrame::EOFdti,
+        0x4F => EndOfFrame::EOFrti,
+        0x50 => EndOfFrame::EOFa,
+    }
+}
+
+fn read_u24(bytes: &[u8]) -> u32 {
+    ((bytes[0] as u32) << 16) + ((bytes[1] as u32) << 8) + (bytes[2] as u32)
+}
+
+/// The 24 byte Fibre Channel frame header.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FcHeader {
+    bytes: [u8;24],
+}
+
+impl FcHeader {
+    pub fn from_slice(slice: &[u8]) -> FcHeader {
+        assert!(slice.len() == FC_HEADER_LEN);
+        let mut bytes = [0u8;24];
+        bytes.copy_from_slice(slice);
+        FcHeader { bytes }
+    }
+
+    pub fn get_r_ctl(&self) -> u8 {
+        self.bytes[0]
+    }
+
+    pub fn get_d_id(&self) -> u32 {
+        read_u24(&self.bytes[1..4])
+    }
+
+    pub fn get_cs_ctl(&self) -> u8 {
+        self.bytes[4]
+    }
+
+    pub fn get_s_id(&self) -> u32 {
+        read_u24(&self.bytes[5..8])
+    }
+
+    pub fn get_type(&self) -> u8 {
+        self.bytes[8]
+    }
+
+    pub fn get_f_ctl(&self) -> u32 {
+        read_u24(&self.bytes[9..12])
+    }
+
+    pub fn get_seq_id(&self) -> u8 {
+        self.bytes[12]
+    }
+
+    pub fn get_df_ctl(&self) -> u8 {
+        self.bytes[13]
+    }
+
+    pub fn get_seq_cnt(&self) -> u16 {
+        ((self.bytes[14] as u16) << 8) + (self.bytes[15] as u16)
+    }
+
+    pub fn get_ox_id(&self) -> u16 {
+        ((self.bytes[16] as u16) << 8) + (self.bytes[17] as u16)
+    }
+
+    pub fn get_rx_id(&self) -> u16 {
+        ((self.bytes[18] as u16) << 8) + (self.bytes[19] as u16)
+    }
+
+    pub fn get_parameter(&self) -> u32 {
+        ((self.bytes[20] as u32) << 24) + ((self.bytes[21] as u32) << 16) + ((self.bytes[22] as u32) << 8) + (self.bytes[23] as u32)
+    }
+
+    pub fn to_bytes(&self) -> [u8;24] {
+        self.bytes
+    }
+}
+
+/// Builds the Fabric Provided MAC Address for an FC_ID: the FC-MAP followed by the FC_ID.
+pub fn fpma(fc_map: [u8;3], fc_id: u32) -> MacAddress {
+    MacAddress::from_bytes([fc_map[0], fc_map[1], fc_map[2], (fc_id >> 16) as u8, (fc_id >> 8) as u8, fc_id as u8])
+}
+
+/// An FCoE frame split into its encapsulation and the Fibre Channel frame inside.
+#[derive(Clone, Copy, Debug)]
+pub struct FcoeView<'a> {
+    src_mac: MacAddress,
+    dst_mac: MacAddress,
+    version: u8,
+    sof: u8,
+    fc_header: FcHeader,
+    fc_payload: &'a [u8],
+    fc_crc: &'a [u8],
+    eof: u8,
+}
+
+impl<'a> FcoeView<'a> {
+    pub fn from_frame(frame: &FrameView<'a>) -> Result<FcoeView<'a>, FcoeError> {
+        match frame.get_ethertype() {
+            Ok(NetworkProtocol::FCoE) => (),
+            _ => return Err(FcoeError::NotFcoe(frame.get_ethertype_raw())),
+        }
+        let bytes = frame.get_network_payload();
+        let needed = FCOE_HEADER_LEN + FC_HEADER_LEN + FCOE_TRAILER_LEN;
+        if bytes.len() < needed {
+            return Err(FcoeError::Truncated { needed, actual: bytes.len() });
+        }
+        let fc_start = FCOE_HEADER_LEN;
+        let crc_start = bytes.len() - FCOE_TRAILER_LEN;
+        Ok(FcoeView {
+            src_mac: frame.get_src_mac(),
+            dst_mac: frame.get_dst_mac(),
+            version: bytes[0] >> 4,
+            sof: bytes[FCOE_HEADER_LEN - 1],
+            fc_header: FcHeader::from_slice(&bytes[fc_start..fc_start + FC_HEADER_LEN]),
+            fc_payload: &bytes[fc_start + FC_HEADER_LEN..crc_start],
+            fc_crc: &bytes[crc_start..crc_start + 4],
+            eof: bytes[crc_start + 4],
+        })
+    }
+
+    pub fn get_version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn get_sof_raw(&self) -> u8 {
+        self.sof
+    }
+
+    pub fn get_sof(&self) -> Option<StartOfFrame> {
+        StartOfFrameDelimiters(self.sof).cloned()
+    }
+
+    pub fn get_eof_raw(&self) -> u8 {
+        self.eof
+    }
+
+    pub fn get_eof(&self) -> Option<EndOfFrame> {
+        EndOfFrameDelimiters(self.eof).cloned()
+    }
+
+    pub fn get_fc_header(&self) -> FcHeader {
+        self.fc_header
+    }
+
+    pub fn get_fc_payload(&self) -> &'a [u8] {
+        self.fc_payload
+    }
+
+    /// Checks the FC CRC, which uses the same CRC-32 and byte order as the Ethernet FCS.
+    pub fn verify_fc_crc(&self) -> bool {
+        let mut fc_frame = self.fc_header.to_bytes().to_vec();
+        fc_frame.extend_from_slice(self.fc_payload);
+        FCS::from_slice(self.fc_crc).matches(&fc_frame)
+    }
+
+    pub fn get_src_mac(&self) -> MacAddress {
+        self.src_mac
+    }
+
+    pub fn get_dst_mac(&self) -> MacAddress {
+        self.dst_mac
+    }
+
+    /// Whether the source MAC is the FPMA of the frame's S_ID under `fc_map`. A mismatch
+    /// means the ENode is not using the address the fabric assigned it.
+    pub fn src_mac_matches_s_id(&self, fc_map: [u8;3]) -> bool {
+        self.src_mac == fpma(fc_map, self.fc_header.get_s_id())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::ethernet::{FrameBuilder,LlcHeader,SnapHeader,SAP_SNAP,OUI_RFC1042};
+
+    fn fcoe_payload(fc_payload: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0u8; 13];
+        bytes.push(0x2E);
+        let fc_header = [0x06, 0x01, 0x02, 0x03, 0x00, 0x0A, 0x0B, 0x0C, 0x08, 0x29, 0x00, 0x00,
+                         0x01, 0x00, 0x00, 0x02, 0x12, 0x34, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x10];
+        let mut fc_frame = fc_header.to_vec();
+        fc_frame.extend_from_slice(fc_payload);
+        let crc = FCS::compute(&fc_frame);
+        bytes.extend(fc_frame);
+        bytes.extend_from_slice(&crc.to_bytes());
+        bytes.extend_from_slice(&[0x42, 0, 0, 0]);
+        bytes
+    }
+
+    #[test]
+    fn parses_fcoe_frame() {
+        let src = fpma(DEFAULT_FC_MAP, 0x0A0B0C);
+        let dst = fpma(DEFAULT_FC_MAP, 0x010203);
+        let bytes = FrameBuilder::new(dst, src, NetworkProtocol::FCoE).payload(&fcoe_payload(&[0x55; 32])).build();
+        let fcoe = FcoeView::from_frame(&FrameView::new(&bytes).unwrap()).unwrap();
+
+        assert_eq!(fcoe.get_sof(), Some(StartOfFrame::SOFi3));
+        assert_eq!(fcoe.get_eof(), Some(EndOfFrame::EOFt));
+        let header = fcoe.get_fc_header();
+        assert_eq!(header.get_d_id(), 0x010203);
+        assert_eq!(header.get_s_id(), 0x0A0B0C);
+        assert_eq!(header.get_type(), 0x08);
+        assert_eq!(header.get_ox_id(), 0x1234);
+        assert_eq!(header.get_parameter(), 0x10);
+        assert_eq!(fcoe.get_fc_payload().len(), 32);
+        assert!(fcoe.verify_fc_crc());
+        assert!(fcoe.src_mac_matches_s_id(DEFAULT_FC_MAP));
+
+        let mut snap = LlcHeader::new(SAP_SNAP, SAP_SNAP, 0x03).to_bytes();
+        snap.extend_from_slice(&SnapHeader::new(OUI_RFC1042, 0x8906).to_bytes());
+        snap.extend_from_slice(&fcoe_payload(&[0x55; 32]));
+        let bytes = FrameBuilder::ieee8023(dst, src).payload(&snap).build();
+        let fcoe = FcoeView::from_frame(&FrameView::new(&bytes).unwrap()).unwrap();
+        assert_eq!((fcoe.get_sof(), fcoe.get_eof()), (Some(StartOfFrame::SOFi3), Some(EndOfFrame::EOFt)));
+        assert_eq!(fcoe.get_fc_header().get_ox_id(), 0x1234);
+        assert!(fcoe.verify_fc_crc());
+    }
+
+    #[test]
+    fn rejects_short_frames() {
+        let mac = MacAddress::from_bytes([0x0E, 0xFC, 0, 1, 2, 3]);
+        let bytes = FrameBuilder::new(mac, mac, NetworkProtocol::FCoE).payload(&fcoe_payload(&[])).build();
+        assert_eq!(FcoeView::from_frame(&FrameView::new(&bytes[..44]).unwrap()).unwrap_err(), FcoeError::Truncated { needed: 46, actual: 30 });
+    }
+}
@@ -0,0 +1,738 @@
+use std::error::Error;
+use std::fmt;
+use std::net::{Ipv4Addr,Ipv6Addr};
+
+use layer3::NetworkProtocol;
+use super::ethernet::{FrameBuilder,FrameView,MacAddress,MAC_NEAREST_BRIDGE};
+
+// Nearest-bridge group address. LLDPDUs sent here are never forwarded.
+pub const LLDP_MULTICAST: [u8;6] = MAC_NEAREST_BRIDGE;
+
+pub const OUI_IEEE_802_1: [u8;3] = [0x00, 0x80, 0xC2];
+pub const OUI_IEEE_802_3: [u8;3] = [0x00, 0x12, 0x0F];
+
+const TLV_END: u8 = 0;
+const TLV_CHASSIS_ID: u8 = 1;
+const TLV_PORT_ID: u8 = 2;
+const TLV_TTL: u8 = 3;
+const TLV_PORT_DESCRIPTION: u8 = 4;
+const TLV_SYSTEM_NAME: u8 = 5;
+const TLV_SYSTEM_DESCRIPTION: u8 = 6;
+const TLV_SYSTEM_CAPABILITIES: u8 = 7;
+const TLV_MANAGEMENT_ADDRESS: u8 = 8;
+const TLV_ORG_SPECIFIC: u8 = 127;
+
+const MAX_TLV_LEN: usize = 511;
+// Limits on length-prefixed fields inside TLVs, from 802.1AB and 802.1Q Annex D.
+const MAX_MANAGEMENT_ADDRESS_LEN: usize = 32;   // Address family byte included
+const MAX_OID_LEN: usize = 128;
+const MAX_VLAN_NAME_LEN: usize = 32;
+const MAX_PROTOCOL_IDENTITY_LEN: usize = 255;
+
+// IANA address family numbers, as used in network address subtypes.
+const FAMILY_IPV4: u8 = 1;
+const FAMILY_IPV6: u8 = 2;
+const FAMILY_802: u8 = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LldpError {
+    Truncated { needed: usize, actual: usize },
+    // The first three TLVs must be Chassis ID, Port ID and TTL, in that order.
+    MissingMandatoryTlv(u8),
+    // A TLV is too short for its type.
+    BadTlvLength { tlv_type: u8, length: usize },
+    // A value is too long to encode in its TLV or length-prefixed field.
+    ValueTooLong { tlv_type: u8, length: usize, max: usize },
+    // An Unknown TLV whose type is End, has its own variant, or doesn't fit in 7 bits.
+    InvalidTlvType(u8),
+    NotLldp(u16),
+}
+
+impl Error for LldpError {
+    fn description(&self) -> &str {
+        match *self {
+            LldpError::Truncated { .. } => "LLDPDU is truncated.",
+            LldpError::MissingMandatoryTlv(_) => "LLDPDU is missing a mandatory TLV.",
+            LldpError::BadTlvLength { .. } => "LLDP TLV length is invalid for its type.",
+            LldpError::ValueTooLong { .. } => "LLDP TLV value is too long to encode.",
+            LldpError::InvalidTlvType(_) => "LLDP TLV type is not a reserved type.",
+            LldpError::NotLldp(_) => "Frame does not carry an LLDPDU.",
+        }
+    }
+}
+
+impl fmt::Display for LldpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LldpError::Truncated { needed, actual } => write!(f,"LLDPDU is truncated. Needed {} bytes, got {}", needed, actual),
+            LldpError::MissingMandatoryTlv(val) => write!(f,"LLDPDU is missing a mandatory TLV. Expected TLV type: {}", val),
+            LldpError::BadTlvLength { tlv_type, length } => write!(f,"LLDP TLV length is invalid for its type. Type: {}, length: {}", tlv_type, length),
+            LldpError::ValueTooLong { tlv_type, length, max } => write!(f,"LLDP TLV value is too long to encode. Type: {}, length: {}, max: {}", tlv_type, length, max),
+            LldpError::InvalidTlvType(val) => write!(f,"LLDP TLV type is not a reserved type. Type: {}", val),
+            LldpError::NotLldp(val) => write!(f,"Frame does not carry an LLDPDU. EtherType: {:04X}", val),
+        }
+    }
+}
+
+fn read_u16(bytes: &[u8]) -> u16 {
+    ((bytes[0] as u16) << 8) + (bytes[1] as u16)
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    ((bytes[0] as u32) << 24) + ((bytes[1] as u32) << 16) + ((bytes[2] as u32) << 8) + (bytes[3] as u32)
+}
+
+fn to_text(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+// A length prefix for `len` bytes, or an error if they don't fit in `max`.
+fn length_byte(tlv_type: u8, len: usize, max: usize) -> Result<u8, LldpError> {
+    if len > max {
+        Err(LldpError::ValueTooLong { tlv_type, length: len, max })
+    }
+    else {
+        Ok(len as u8)
+    }
+}
+
+fn check_len(tlv_type: u8, info: &[u8], min: usize) -> Result<(), LldpError> {
+    if info.len() < min {
+        Err(LldpError::BadTlvLength { tlv_type, length: info.len() })
+    }
+    else {
+        Ok(())
+    }
+}
+
+/// An address tagged with its IANA address family, as carried in Chassis ID, Port ID and
+/// Management Address TLVs.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NetworkAddress {
+    IPv4(Ipv4Addr),
+    IPv6(Ipv6Addr),
+    Mac(MacAddress),
+    Other(u8, Vec<u8>),
+}
+
+impl NetworkAddress {
+    fn parse(bytes: &[u8]) -> Option<NetworkAddress> {
+        let (family, addr) = match bytes.split_first() {
+            Some((family, addr)) => (*family, addr),
+            None => return None,
+        };
+        Some(match (family, addr.len()) {
+            (FAMILY_IPV4, 4) => NetworkAddress::IPv4(Ipv4Addr::new(addr[0], addr[1], addr[2], addr[3])),
+            (FAMILY_IPV6, 16) => {
+                let mut octets = [0u8;16];
+                octets.copy_from_slice(addr);
+                NetworkAddress::IPv6(Ipv6Addr::from(octets))
+            },
+            (FAMILY_802, 6) => NetworkAddress::Mac(MacAddress::from_slice(addr)),
+            _ => NetworkAddress::Other(family, addr.to_vec()),
+        })
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        match *self {
+            NetworkAddress::IPv4(ref addr) => {
+                bytes.push(FAMILY_IPV4);
+                bytes.extend_from_slice(&addr.octets());
+            },
+            NetworkAddress::IPv6(ref addr) => {
+                bytes.push(FAMILY_IPV6);
+                bytes.extend_from_slice(&addr.octets());
+            },
+            NetworkAddress::Mac(ref addr) => {
+                bytes.push(FAMILY_802);
+                bytes.extend_from_slice(&addr.to_bytes());
+            },
+            NetworkAddress::Other(family, ref addr) => {
+                bytes.push(family);
+                bytes.extend_from_slice(addr);
+            },
+        }
+        bytes
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChassisId {
+    ChassisComponent(String),
+    InterfaceAlias(String),
+    PortComponent(String),
+    MacAddress(MacAddress),
+    NetworkAddress(NetworkAddress),
+    InterfaceName(String),
+    LocallyAssigned(String),
+    // Reserved subtypes, or a MAC or network address of the wrong length.
+    Other(u8, Vec<u8>),
+}
+
+impl ChassisId {
+    fn parse(info: &[u8]) -> Result<ChassisId, LldpError> {
+        check_len(TLV_CHASSIS_ID, info, 2)?;
+        let (subtype, id) = (info[0], &info[1..]);
+        Ok(match subtype {
+            1 => ChassisId::ChassisComponent(to_text(id)),
+            2 => ChassisId::InterfaceAlias(to_text(id)),
+            3 => ChassisId::PortComponent(to_text(id)),
+            4 if id.len() == 6 => ChassisId::MacAddress(MacAddress::from_slice(id)),
+            5 => match NetworkAddress::parse(id) {
+                Some(addr) => ChassisId::NetworkAddress(addr),
+                None => ChassisId::Other(subtype, id.to_vec()),
+            },
+            6 => ChassisId::InterfaceName(to_text(id)),
+            7 => ChassisId::LocallyAssigned(to_text(id)),
+            _ => ChassisId::Other(subtype, id.to_vec()),
+        })
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let (subtype, id) = match *self {
+            ChassisId::ChassisComponent(ref x) => (1, x.as_bytes().to_vec()),
+            ChassisId::InterfaceAlias(ref x) => (2, x.as_bytes().to_vec()),
+            ChassisId::PortComponent(ref x) => (3, x.as_bytes().to_vec()),
+            ChassisId::MacAddress(ref x) => (4, x.to_bytes().to_vec()),
+            ChassisId::NetworkAddress(ref x) => (5, x.to_bytes()),
+            ChassisId::InterfaceName(ref x) => (6, x.as_bytes().to_vec()),
+            ChassisId::LocallyAssigned(ref x) => (7, x.as_bytes().to_vec()),
+            ChassisId::Other(subtype, ref x) => (subtype, x.clone()),
+        };
+        let mut bytes = vec![subtype];
+        bytes.extend(id);
+        bytes
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PortId {
+    InterfaceAlias(String),
+    PortComponent(String),
+    MacAddress(MacAddress),
+    NetworkAddress(NetworkAddress),
+    InterfaceName(String),
+    AgentCircuitId(Vec<u8>),
+    LocallyAssigned(String),
+    // Reserved subtypes, or a MAC or network address of the wrong length.
+    Other(u8, Vec<u8>),
+}
+
+impl PortId {
+    fn parse(info: &[u8]) -> Result<PortId, LldpError> {
+        check_len(TLV_PORT_ID, info, 2)?;
+        let (subtype, id) = (info[0], &info[1..]);
+        Ok(match subtype {
+            1 => PortId::InterfaceAlias(to_text(id)),
+            2 => PortId::PortComponent(to_text(id)),
+            3 if id.len() == 6 => PortId::MacAddress(MacAddress::from_slice(id)),
+            4 => match NetworkAddress::parse(id) {
+                Some(addr) => PortId::NetworkAddress(addr),
+                None => PortId::Other(subtype, id.to_vec()),
+            },
+            5 => PortId::InterfaceName(to_text(id)),
+            6 => PortId::AgentCircuitId(id.to_vec()),
+            7 => PortId::LocallyAssigned(to_text(id)),
+            _ => PortId::Other(subtype, id.to_vec()),
+        })
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let (subtype, id) = match *self {
+            PortId::InterfaceAlias(ref x) => (1, x.as_bytes().to_vec()),
+            PortId::PortComponent(ref x) => (2, x.as_bytes().to_vec()),
+            PortId::MacAddress(ref x) => (3, x.to_bytes().to_vec()),
+            PortId::NetworkAddress(ref x) => (4, x.to_bytes()),
+            PortId::InterfaceName(ref x) => (5, x.as_bytes().to_vec()),
+            PortId::AgentCircuitId(ref x) => (6, x.clone()),
+            PortId::LocallyAssigned(ref x) => (7, x.as_bytes().to_vec()),
+            PortId::Other(subtype, ref x) => (subtype, x.clone()),
+        };
+        let mut bytes = vec![subtype];
+        bytes.extend(id);
+        bytes
+    }
+}
+
+/// System capability bitmaps. Use the CAP_* constants to test individual bits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SystemCapabilities {
+    supported: u16,
+    enabled: u16,
+}
+
+impl SystemCapabilities {
+    pub const CAP_OTHER: u16 = 0x0001;
+    pub const CAP_REPEATER: u16 = 0x0002;
+    pub const CAP_BRIDGE: u16 = 0x0004;
+    pub const CAP_WLAN_AP: u16 = 0x0008;
+    pub const CAP_ROUTER: u16 = 0x0010;
+    pub const CAP_TELEPHONE: u16 = 0x0020;
+    pub const CAP_DOCSIS: u16 = 0x0040;
+    pub const CAP_STATION: u16 = 0x0080;
+    pub const CAP_C_VLAN: u16 = 0x0100;
+    pub const CAP_S_VLAN: u16 = 0x0200;
+    pub const CAP_TPMR: u16 = 0x0400;
+
+    pub fn new(supported: u16, enabled: u16) -> SystemCapabilities {
+        SystemCapabilities { supported, enabled }
+    }
+
+    pub fn get_supported(&self) -> u16 {
+        self.supported
+    }
+
+    pub fn get_enabled(&self) -> u16 {
+        self.enabled
+    }
+
+    pub fn supports(&self, cap: u16) -> bool {
+        self.supported & cap == cap
+    }
+
+    pub fn is_enabled(&self, cap: u16) -> bool {
+        self.enabled & cap == cap
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ManagementAddress {
+    address: NetworkAddress,
+    if_subtype: u8,
+    if_number: u32,
+    oid: Vec<u8>,
+}
+
+impl ManagementAddress {
+    // Interface numbering subtypes.
+    pub const IF_UNKNOWN: u8 = 1;
+    pub const IF_INDEX: u8 = 2;
+    pub const IF_SYSTEM_PORT: u8 = 3;
+
+    pub fn new(address: NetworkAddress, if_subtype: u8, if_number: u32) -> ManagementAddress {
+        ManagementAddress { address, if_subtype, if_number, oid: Vec::new() }
+    }
+
+    fn parse(info: &[u8]) -> Result<ManagementAddress, LldpError> {
+        let bad_length = LldpError::BadTlvLength { tlv_type: TLV_MANAGEMENT_ADDRESS, length: info.len() };
+        check_len(TLV_MANAGEMENT_ADDRESS, info, 9)?;
+        let addr_len = info[0] as usize;
+        if addr_len < 2 || info.len() < 1 + addr_len + 6 {
+            return Err(bad_length);
+        }
+        let address = NetworkAddress::parse(&info[1..1 + addr_len]).ok_or(bad_length)?;
+        let rest = &info[1 + addr_len..];
+        let oid_len = rest[5] as usize;
+        if rest.len() < 6 + oid_len {
+            return Err(bad_length);
+        }
+        Ok(ManagementAddress { address, if_subtype: rest[0], if_number: read_u32(&rest[1..5]), oid: rest[6..6 + oid_len].to_vec() })
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>, LldpError> {
+        let addr = self.address.to_bytes();
+        let mut bytes = vec![length_byte(TLV_MANAGEMENT_ADDRESS, addr.len(), MAX_MANAGEMENT_ADDRESS_LEN)?];
+        bytes.extend(addr);
+        bytes.push(self.if_subtype);
+        bytes.extend_from_slice(&self.if_number.to_be_bytes());
+        bytes.push(length_byte(TLV_MANAGEMENT_ADDRESS, self.oid.len(), MAX_OID_LEN)?);
+        bytes.extend_from_slice(&self.oid);
+        Ok(bytes)
+    }
+
+    pub fn get_address(&self) -> &NetworkAddress {
+        &self.address
+    }
+
+    pub fn get_if_subtype(&self) -> u8 {
+        self.if_subtype
+    }
+
+    pub fn get_if_number(&self) -> u32 {
+        self.if_number
+    }
+
+    // BER encoded, and usually empty.
+    pub fn get_oid(&self) -> &[u8] {
+        &self.oid
+    }
+}
+
+/// Organizationally specific TLVs. The IEEE 802.1 and 802.3 ones are decoded, anything else
+/// is kept as raw bytes.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OrgTlv {
+    // IEEE 802.1
+    PortVlanId(u16),
+    PortProtocolVlanId { flags: u8, vlan_id: u16 },
+    VlanName { vlan_id: u16, name: String },
+    ProtocolIdentity(Vec<u8>),
+    // IEEE 802.3
+    MacPhyConfig { autoneg: u8, pmd_advertised: u16, mau_type: u16 },
+    PowerViaMdi(Vec<u8>),
+    LinkAggregation { status: u8, port_id: u32 },
+    MaxFrameSize(u16),
+    Unknown { oui: [u8;3], subtype: u8, info: Vec<u8> },
+}
+
+impl OrgTlv {
+    fn parse(info: &[u8]) -> Result<OrgTlv, LldpError> {
+        check_len(TLV_ORG_SPECIFIC, info, 4)?;
+        let oui = [info[0], info[1], info[2]];
+        let (subtype, data) = (info[3], &info[4..]);
+        let bad_length = LldpError::BadTlvLength { tlv_type: TLV_ORG_SPECIFIC, length: info.len() };
+        let tlv = if oui == OUI_IEEE_802_1 {
+            match subtype {
+                1 if data.len() >= 2 => OrgTlv::PortVlanId(read_u16(data)),
+                2 if data.len() >= 3 => OrgTlv::PortProtocolVlanId { flags: data[0], vlan_id: read_u16(&data[1..]) },
+                3 if data.len() >= 3 => {
+                    let name_len = data[2] as usize;
+                    if data.len() < 3 + name_len {
+                        return Err(bad_length);
+                    }
+                    OrgTlv::VlanName { vlan_id: read_u16(data), name: to_text(&data[3..3 + name_len]) }
+                },
+                4 if !data.is_empty() && data.len() > data[0] as usize => OrgTlv::ProtocolIdentity(data[1..1 + data[0] as usize].to_vec()),
+                1..=4 => return Err(bad_length),
+                _ => OrgTlv::Unknown { oui, subtype, info: data.to_vec() },
+            }
+        }
+        else if oui == OUI_IEEE_802_3 {
+            match subtype {
+                1 if data.len() >= 5 => OrgTlv::MacPhyConfig { autoneg: data[0], pmd_advertised: read_u16(&data[1..]), mau_type: read_u16(&data[3..]) },
+                2 => OrgTlv::PowerViaMdi(data.to_vec()),
+                3 if data.len() >= 5 => OrgTlv::LinkAggregation { status: data[0], port_id: read_u32(&data[1..]) },
+                4 if data.len() >= 2 => OrgTlv::MaxFrameSize(read_u16(data)),
+                1 | 3 | 4 => return Err(bad_length),
+                _ => OrgTlv::Unknown { oui, subtype, info: data.to_vec() },
+            }
+        }
+        else {
+            OrgTlv::Unknown { oui, subtype, info: data.to_vec() }
+        };
+        Ok(tlv)
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>, LldpError> {
+        let (oui, subtype, data) = match *self {
+            OrgTlv::PortVlanId(vlan_id) => (OUI_IEEE_802_1, 1, vlan_id.to_be_bytes().to_vec()),
+            OrgTlv::PortProtocolVlanId { flags, vlan_id } => (OUI_IEEE_802_1, 2, vec![flags, (vlan_id >> 8) as u8, vlan_id as u8]),
+            OrgTlv::VlanName { vlan_id, ref name } => {
+                let mut data = vec![(vlan_id >> 8) as u8, vlan_id as u8, length_byte(TLV_ORG_SPECIFIC, name.len(), MAX_VLAN_NAME_LEN)?];
+                data.extend_from_slice(name.as_bytes());
+                (OUI_IEEE_802_1, 3, data)
+            },
+            OrgTlv::ProtocolIdentity(ref id) => {
+                let mut data = vec![length_byte(TLV_ORG_SPECIFIC, id.len(), MAX_PROTOCOL_IDENTITY_LEN)?];
+                data.extend_from_slice(id);
+                (OUI_IEEE_802_1, 4, data)
+            },
+            OrgTlv::MacPhyConfig { autoneg, pmd_advertised, mau_type } => {
+                let mut data = vec![autoneg];
+                data.extend_from_slice(&pmd_advertised.to_be_bytes());
+                data.extend_from_slice(&mau_type.to_be_bytes());
+                (OUI_IEEE_802_3, 1, data)
+            },
+            OrgTlv::PowerViaMdi(ref data) => (OUI_IEEE_802_3, 2, data.clone()),
+            OrgTlv::LinkAggregation { status, port_id } => {
+                let mut data = vec![status];
+                data.extend_from_slice(&port_id.to_be_bytes());
+                (OUI_IEEE_802_3, 3, data)
+            },
+            OrgTlv::MaxFrameSize(size) => (OUI_IEEE_802_3, 4, size.to_be_bytes().to_vec()),
+            OrgTlv::Unknown { oui, subtype, ref info } => (oui, subtype, info.clone()),
+        };
+        let mut bytes = oui.to_vec();
+        bytes.push(subtype);
+        bytes.extend(data);
+        Ok(bytes)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LldpTlv {
+    ChassisId(ChassisId),
+    PortId(PortId),
+    Ttl(u16),
+    PortDescription(String),
+    SystemName(String),
+    SystemDescription(String),
+    SystemCapabilities(SystemCapabilities),
+    ManagementAddress(ManagementAddress),
+    OrgSpecific(OrgTlv),
+    // Reserved TLV types, 9 to 126, kept so they survive a decode and re-encode.
+    Unknown(u8, Vec<u8>),
+}
+
+impl LldpTlv {
+    fn parse(tlv_type: u8, info: &[u8]) -> Result<LldpTlv, LldpError> {
+        Ok(match tlv_type {
+            TLV_CHASSIS_ID => LldpTlv::ChassisId(ChassisId::parse(info)?),
+            TLV_PORT_ID => LldpTlv::PortId(PortId::parse(info)?),
+            TLV_TTL => {
+                check_len(tlv_type, info, 2)?;
+                LldpTlv::Ttl(read_u16(info))
+            },
+            TLV_PORT_DESCRIPTION => LldpTlv::PortDescription(to_text(info)),
+            TLV_SYSTEM_NAME => LldpTlv::SystemName(to_text(info)),
+            TLV_SYSTEM_DESCRIPTION => LldpTlv::SystemDescription(to_text(info)),
+            TLV_SYSTEM_CAPABILITIES => {
+                check_len(tlv_type, info, 4)?;
+                LldpTlv::SystemCapabilities(SystemCapabilities::new(read_u16(info), read_u16(&info[2..])))
+            },
+            TLV_MANAGEMENT_ADDRESS => LldpTlv::ManagementAddress(ManagementAddress::parse(info)?),
+            TLV_ORG_SPECIFIC => LldpTlv::OrgSpecific(OrgTlv::parse(info)?),
+            _ => LldpTlv::Unknown(tlv_type, info.to_vec()),
+        })
+    }
+
+    pub fn get_type(&self) -> u8 {
+        match *self {
+            LldpTlv::ChassisId(_) => TLV_CHASSIS_ID,
+            LldpTlv::PortId(_) => TLV_PORT_ID,
+            LldpTlv::Ttl(_) => TLV_TTL,
+            LldpTlv::PortDescription(_) => TLV_PORT_DESCRIPTION,
+            LldpTlv::SystemName(_) => TLV_SYSTEM_NAME,
+            LldpTlv::SystemDescription(_) => TLV_SYSTEM_DESCRIPTION,
+            LldpTlv::SystemCapabilities(_) => TLV_SYSTEM_CAPABILITIES,
+            LldpTlv::ManagementAddress(_) => TLV_MANAGEMENT_ADDRESS,
+            LldpTlv::OrgSpecific(_) => TLV_ORG_SPECIFIC,
+            LldpTlv::Unknown(tlv_type, _) => tlv_type,
+        }
+    }
+
+    /// Encodes the TLV with its header. Fails if the value is longer than the 511 bytes a TLV
+    /// can hold, or a field inside it is longer than its length prefix allows, or if an
+    /// Unknown TLV's type is not one of the reserved ones.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, LldpError> {
+        if let LldpTlv::Unknown(tlv_type, _) = *self {
+            if tlv_type <= TLV_MANAGEMENT_ADDRESS || tlv_type >= TLV_ORG_SPECIFIC {
+                return Err(LldpError::InvalidTlvType(tlv_type));
+            }
+        }
+        let info = match *self {
+            LldpTlv::ChassisId(ref x) => x.to_bytes(),
+            LldpTlv::PortId(ref x) => x.to_bytes(),
+            LldpTlv::Ttl(x) => x.to_be_bytes().to_vec(),
+            LldpTlv::PortDescription(ref x) | LldpTlv::SystemName(ref x) | LldpTlv::SystemDescription(ref x) => x.as_bytes().to_vec(),
+            LldpTlv::SystemCapabilities(ref x) => {
+                let mut info = x.supported.to_be_bytes().to_vec();
+                info.extend_from_slice(&x.enabled.to_be_bytes());
+                info
+            },
+            LldpTlv::ManagementAddress(ref x) => x.to_bytes()?,
+            LldpTlv::OrgSpecific(ref x) => x.to_bytes()?,
+            LldpTlv::Unknown(_, ref x) => x.clone(),
+        };
+        if info.len() > MAX_TLV_LEN {
+            return Err(LldpError::ValueTooLong { tlv_type: self.get_type(), length: info.len(), max: MAX_TLV_LEN });
+        }
+        let header = ((self.get_type() as u16) << 9) | info.len() as u16;
+        let mut bytes = header.to_be_bytes().to_vec();
+        bytes.extend(info);
+        Ok(bytes)
+    }
+}
+
+/// A decoded LLDP data unit. The mandatory TLVs have their own fields, and everything after
+/// them is kept in order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lldpdu {
+    chassis_id: ChassisId,
+    port_id: PortId,
+    ttl: u16,
+    tlvs: Vec<LldpTlv>,
+}
+
+impl Lldpdu {
+    pub fn new(chassis_id: ChassisId, port_id: PortId, ttl: u16) -> Lldpdu {
+        Lldpdu { chassis_id, port_id, ttl, tlvs: Vec::new() }
+    }
+
+    /// Decodes TLVs up to the End TLV, or to the end of `bytes` if there isn't one.
+    pub fn parse(bytes: &[u8]) -> Result<Lldpdu, LldpError> {
+        let mut tlvs = Vec::new();
+        let mut offset = 0;
+        while offset < bytes.len() {
+            if bytes.len() < offset + 2 {
+                return Err(LldpError::Truncated { needed: offset + 2, actual: bytes.len() });
+            }
+            let header = read_u16(&bytes[offset..]);
+            let (tlv_type, length) = ((header >> 9) as u8, (header & 0x01FF) as usize);
+            offset += 2;
+            if bytes.len() < offset + length {
+                return Err(LldpError::Truncated { needed: offset + length, actual: bytes.len() });
+            }
+            if tlv_type == TLV_END {
+                break;
+            }
+            tlvs.push(LldpTlv::parse(tlv_type, &bytes[offset..offset + length])?);
+            offset += length;
+        }
+
+        let mut tlvs = tlvs.into_iter();
+        let chassis_id = match tlvs.next() {
+            Some(LldpTlv::ChassisId(x)) => x,
+            _ => return Err(LldpError::MissingMandatoryTlv(TLV_CHASSIS_ID)),
+        };
+        let port_id = match tlvs.next() {
+            Some(LldpTlv::PortId(x)) => x,
+            _ => return Err(LldpError::MissingMandatoryTlv(TLV_PORT_ID)),
+        };
+        let ttl = match tlvs.next() {
+            Some(LldpTlv::Ttl(x)) => x,
+            _ => return Err(LldpError::MissingMandatoryTlv(TLV_TTL)),
+        };
+        Ok(Lldpdu { chassis_id, port_id, ttl, tlvs: tlvs.collect() })
+    }
+
+    pub fn from_frame(frame: &FrameView) -> Result<Lldpdu, LldpError> {
+        match frame.get_ethertype() {
+            Ok(NetworkProtocol::LLDP) => Lldpdu::parse(frame.get_network_payload()),
+            _ => Err(LldpError::NotLldp(frame.get_ethertype_raw())),
+        }
+    }
+
+    /// Appends an optional TLV. Mandatory TLVs passed here are encoded again after the real
+    /// ones, which receivers will reject.
+    pub fn push(&mut self, tlv: LldpTlv) {
+        self.tlvs.push(tlv);
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, LldpError> {
+        let mut bytes = LldpTlv::ChassisId(self.chassis_id.clone()).to_bytes()?;
+        bytes.extend(LldpTlv::PortId(self.port_id.clone()).to_bytes()?);
+        bytes.extend(LldpTlv::Ttl(self.ttl).to_bytes()?);
+        for tlv in &self.tlvs {
+            bytes.extend(tlv.to_bytes()?);
+        }
+        bytes.extend_from_slice(&[0, 0]);
+        Ok(bytes)
+    }
+
+    /// Wraps the LLDPDU in a frame to the nearest-bridge group address.
+    pub fn to_frame(&self, src_mac: MacAddress) -> Result<FrameBuilder, LldpError> {
+        Ok(FrameBuilder::new(MacAddress::from_bytes(LLDP_MULTICAST), src_mac, NetworkProtocol::LLDP).payload(&self.to_bytes()?))
+    }
+
+    pub fn get_chassis_id(&self) -> &ChassisId {
+        &self.chassis_id
+    }
+
+    pub fn get_port_id(&self) -> &PortId {
+        &self.port_id
+    }
+
+    // A TTL of zero means the sender is shutting down and its entry should be removed.
+    pub fn get_ttl(&self) -> u16 {
+        self.ttl
+    }
+
+    /// The optional TLVs, in the order they were received.
+    pub fn get_tlvs(&self) -> &[LldpTlv] {
+        &self.tlvs
+    }
+
+    pub fn get_port_description(&self) -> Option<&str> {
+        self.tlvs.iter().filter_map(|x| match *x { LldpTlv::PortDescription(ref s) => Some(s.as_str()), _ => None }).next()
+    }
+
+    pub fn get_system_name(&self) -> Option<&str> {
+        self.tlvs.iter().filter_map(|x| match *x { LldpTlv::SystemName(ref s) => Some(s.as_str()), _ => None }).next()
+    }
+
+    pub fn get_system_description(&self) -> Option<&str> {
+        self.tlvs.iter().filter_map(|x| match *x { LldpTlv::SystemDescription(ref s) => Some(s.as_str()), _ => None }).next()
+    }
+
+    pub fn get_system_capabilities(&self) -> Option<SystemCapabilities> {
+        self.tlvs.iter().filter_map(|x| match *x { LldpTlv::SystemCapabilities(c) => Some(c), _ => None }).next()
+    }
+
+    pub fn get_management_addresses(&self) -> Vec<&ManagementAddress> {
+        self.tlvs.iter().filter_map(|x| match *x { LldpTlv::ManagementAddress(ref a) => Some(a), _ => None }).collect()
+    }
+
+    pub fn get_org_tlvs(&self) -> Vec<&OrgTlv> {
+        self.tlvs.iter().filter_map(|x| match *x { LldpTlv::OrgSpecific(ref o) => Some(o), _ => None }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Lldpdu {
+        let mut lldpdu = Lldpdu::new(ChassisId::MacAddress(MacAddress::from_bytes([0x00, 0x11, 0x22, 0x33, 0x44, 0x55])),
+                                     PortId::InterfaceName(String::from("ge-0/0/1")), 120);
+        lldpdu.push(LldpTlv::SystemName(String::from("core-sw1")));
+        lldpdu.push(LldpTlv::SystemCapabilities(SystemCapabilities::new(0x0014, 0x0004)));
+        lldpdu.push(LldpTlv::ManagementAddress(ManagementAddress::new(NetworkAddress::IPv4(Ipv4Addr::new(10, 0, 0, 1)), ManagementAddress::IF_INDEX, 7)));
+        lldpdu.push(LldpTlv::OrgSpecific(OrgTlv::PortVlanId(100)));
+        lldpdu.push(LldpTlv::OrgSpecific(OrgTlv::VlanName { vlan_id: 100, name: String::from("users") }));
+        lldpdu.push(LldpTlv::OrgSpecific(OrgTlv::MaxFrameSize(9216)));
+        lldpdu.push(LldpTlv::OrgSpecific(OrgTlv::Unknown { oui: [0x00, 0x00, 0x0C], subtype: 1, info: vec![1, 2] }));
+        lldpdu
+    }
+
+    #[test]
+    fn round_trips() {
+        let lldpdu = sample();
+        let bytes = lldpdu.to_bytes().unwrap();
+        assert_eq!(&bytes[..9], &[0x02, 0x07, 0x04, 0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        assert_eq!(&bytes[bytes.len() - 2..], &[0, 0]);
+        let parsed = Lldpdu::parse(&bytes).unwrap();
+        assert_eq!(parsed, lldpdu);
+        assert_eq!(parsed.get_system_name(), Some("core-sw1"));
+        assert!(parsed.get_system_capabilities().unwrap().is_enabled(SystemCapabilities::CAP_BRIDGE));
+        assert!(!parsed.get_system_capabilities().unwrap().is_enabled(SystemCapabilities::CAP_ROUTER));
+        assert_eq!(parsed.get_management_addresses()[0].get_if_number(), 7);
+        assert_eq!(parsed.get_org_tlvs().len(), 4);
+    }
+
+    #[test]
+    fn parses_from_frame() {
+        let src = MacAddress::from_bytes([0x00, 0x11, 0x22, 0x33, 0x44, 0x56]);
+        let bytes = sample().to_frame(src).unwrap().build();
+        let frame = FrameView::new(&bytes).unwrap();
+        assert_eq!(frame.get_dst_mac(), MacAddress::from_bytes(LLDP_MULTICAST));
+        assert_eq!(Lldpdu::from_frame(&frame).unwrap(), sample());
+    }
+
+    #[test]
+    fn rejects_bad_lldpdus() {
+        let bytes = sample().to_bytes().unwrap();
+        assert_eq!(Lldpdu::parse(&bytes[9..]).unwrap_err(), LldpError::MissingMandatoryTlv(TLV_CHASSIS_ID));
+        assert_eq!(Lldpdu::parse(&bytes[..8]).unwrap_err(), LldpError::Truncated { needed: 9, actual: 8 });
+        assert_eq!(Lldpdu::parse(&[0x06, 0x01, 0x00]).unwrap_err(), LldpError::BadTlvLength { tlv_type: TLV_TTL, length: 1 });
+    }
+
+    #[test]
+    fn refuses_to_truncate_long_values() {
+        let long_name = OrgTlv::VlanName { vlan_id: 1, name: "v".repeat(33) };
+        assert_eq!(LldpTlv::OrgSpecific(long_name).to_bytes().unwrap_err(), LldpError::ValueTooLong { tlv_type: TLV_ORG_SPECIFIC, length: 33, max: 32 });
+        let long_id = OrgTlv::ProtocolIdentity(vec![0; 256]);
+        assert_eq!(LldpTlv::OrgSpecific(long_id).to_bytes().unwrap_err(), LldpError::ValueTooLong { tlv_type: TLV_ORG_SPECIFIC, length: 256, max: 255 });
+        let long_addr = ManagementAddress::new(NetworkAddress::Other(99, vec![0; 300]), ManagementAddress::IF_UNKNOWN, 0);
+        assert_eq!(LldpTlv::ManagementAddress(long_addr).to_bytes().unwrap_err(), LldpError::ValueTooLong { tlv_type: TLV_MANAGEMENT_ADDRESS, length: 301, max: 32 });
+
+        let mut lldpdu = sample();
+        lldpdu.push(LldpTlv::SystemDescription("d".repeat(512)));
+        assert_eq!(lldpdu.to_bytes().unwrap_err(), LldpError::ValueTooLong { tlv_type: TLV_SYSTEM_DESCRIPTION, length: 512, max: 511 });
+        assert!(lldpdu.to_frame(MacAddress::from_bytes([0x02, 0, 0, 0, 0, 1])).is_err());
+    }
+
+    #[test]
+    fn refuses_unknown_tlvs_with_taken_types() {
+        for tlv_type in [TLV_END, TLV_TTL, TLV_MANAGEMENT_ADDRESS, TLV_ORG_SPECIFIC, 200].iter() {
+            assert_eq!(LldpTlv::Unknown(*tlv_type, vec![1]).to_bytes().unwrap_err(), LldpError::InvalidTlvType(*tlv_type));
+        }
+        assert_eq!(LldpTlv::Unknown(9, vec![1]).to_bytes().unwrap(), vec![0x12, 0x01, 0x01]);
+        assert_eq!(LldpTlv::Unknown(126, vec![]).to_bytes().unwrap(), vec![0xFC, 0x00]);
+
+        let mut lldpdu = sample();
+        lldpdu.push(LldpTlv::Unknown(TLV_END, vec![]));
+        assert_eq!(lldpdu.to_bytes().unwrap_err(), LldpError::InvalidTlvType(TLV_END));
+    }
+}
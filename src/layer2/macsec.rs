@@ -0,0 +1,343 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use layer3::NetworkProtocol;
+use super::ethernet::{FrameView,MacAddress};
+
+const SECTAG_LEN: usize = 6;    // TCI/AN, SL and PN. The EtherType is part of the MAC header.
+const SCI_LEN: usize = 8;
+
+// Integrity Check Value length for the default GCM-AES cipher suites.
+pub const DEFAULT_ICV_LEN: usize = 16;
+
+const TCI_VERSION: u8 = 0x80;
+const TCI_END_STATION: u8 = 0x40;
+const TCI_SCI_PRESENT: u8 = 0x20;
+const TCI_SINGLE_COPY_BROADCAST: u8 = 0x10;
+const TCI_ENCRYPTED: u8 = 0x08;
+const TCI_CHANGED_TEXT: u8 = 0x04;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MacsecError {
+    Truncated { needed: usize, actual: usize },
+    // The V bit must be zero in MACsec version 0.
+    UnsupportedVersion,
+    // The short length is set but claims more data than the frame holds.
+    BadShortLength(u8),
+    NotMacsec(u16),
+}
+
+impl Error for MacsecError {
+    fn description(&self) -> &str {
+        match *self {
+            MacsecError::Truncated { .. } => "MACsec frame is truncated.",
+            MacsecError::UnsupportedVersion => "MACsec SecTAG version is not supported.",
+            MacsecError::BadShortLength(_) => "MACsec short length is larger than the frame.",
+            MacsecError::NotMacsec(_) => "Frame does not carry MACsec.",
+        }
+    }
+}
+
+impl fmt::Display for MacsecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MacsecError::Truncated { needed, actual } => write!(f,"MACsec frame is truncated. Needed {} bytes, got {}", needed, actual),
+            MacsecError::UnsupportedVersion => write!(f,"MACsec SecTAG version is not supported. The V bit is set."),
+            MacsecError::BadShortLength(val) => write!(f,"MACsec short length is larger than the frame. Short length: {}", val),
+            MacsecError::NotMacsec(val) => write!(f,"Frame does not carry MACsec. EtherType: {:04X}", val),
+        }
+    }
+}
+
+/// Secure Channel Identifier: the transmitting system's MAC address and a port number.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sci {
+    mac: MacAddress,
+    port: u16,
+}
+
+impl Sci {
+    pub fn new(mac: MacAddress, port: u16) -> Sci {
+        Sci { mac, port }
+    }
+
+    pub fn from_bytes(bytes: [u8;8]) -> Sci {
+        Sci { mac: MacAddress::from_slice(&bytes[..6]), port: ((bytes[6] as u16) << 8) + (bytes[7] as u16) }
+    }
+
+    pub fn to_bytes(&self) -> [u8;8] {
+        let mac = self.mac.to_bytes();
+        [mac[0], mac[1], mac[2], mac[3], mac[4], mac[5], (self.port >> 8) as u8, self.port as u8]
+    }
+
+    pub fn get_mac(&self) -> MacAddress {
+        self.mac
+    }
+
+    pub fn get_port(&self) -> u16 {
+        self.port
+    }
+}
+
+impl fmt::Display for Sci {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.mac, self.port)
+    }
+}
+
+/// The 802.1AE Security TAG that follows the MACsec EtherType.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SecTag {
+    tci_an: u8,
+    short_length: u8,
+    packet_number: u32,
+    sci: Option<Sci>,
+}
+
+impl SecTag {
+    pub fn parse(bytes: &[u8]) -> Result<SecTag, MacsecError> {
+        if bytes.len() < SECTAG_LEN {
+            return Err(MacsecError::Truncated { needed: SECTAG_LEN, actual: bytes.len() });
+        }
+        let tci_an = bytes[0];
+        if tci_an & TCI_VERSION != 0 {
+            return Err(MacsecError::UnsupportedVersion);
+        }
+        let packet_number = ((bytes[2] as u32) << 24) + ((bytes[3] as u32) << 16) + ((bytes[4] as u32) << 8) + (bytes[5] as u32);
+        let sci = if tci_an & TCI_SCI_PRESENT != 0 {
+            if bytes.len() < SECTAG_LEN + SCI_LEN {
+                return Err(MacsecError::Truncated { needed: SECTAG_LEN + SCI_LEN, actual: bytes.len() });
+            }
+            let mut sci = [0u8;8];
+            sci.copy_from_slice(&bytes[SECTAG_LEN..SECTAG_LEN + SCI_LEN]);
+            Some(Sci::from_bytes(sci))
+        }
+        else {
+            None
+        };
+        Ok(SecTag { tci_an, short_length: bytes[1] & 0x3F, packet_number, sci })
+    }
+
+    /// Length of the SecTAG on the wire, not counting the EtherType.
+    pub fn byte_len(&self) -> usize {
+        if self.sci.is_some() { SECTAG_LEN + SCI_LEN } else { SECTAG_LEN }
+    }
+
+    pub fn get_tci(&self) -> u8 {
+        self.tci_an & 0xFC
+    }
+
+    // Association Number, 2 bits.
+    pub fn get_an(&self) -> u8 {
+        self.tci_an & 0x03
+    }
+
+    pub fn is_end_station(&self) -> bool {
+        self.tci_an & TCI_END_STATION != 0
+    }
+
+    pub fn is_single_copy_broadcast(&self) -> bool {
+        self.tci_an & TCI_SINGLE_COPY_BROADCAST != 0
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.tci_an & TCI_ENCRYPTED != 0
+    }
+
+    pub fn is_changed_text(&self) -> bool {
+        self.tci_an & TCI_CHANGED_TEXT != 0
+    }
+
+    /// The number of secure data bytes if fewer than 48, otherwise zero.
+    pub fn get_short_length(&self) -> u8 {
+        self.short_length
+    }
+
+    pub fn get_packet_number(&self) -> u32 {
+        self.packet_number
+    }
+
+    /// The SCI, if it was carried explicitly.
+    pub fn get_sci(&self) -> Option<Sci> {
+        self.sci
+    }
+
+    /// The SCI the frame belongs to. When it is not carried explicitly and the ES bit is set,
+    /// it is the source MAC with port 1. Point-to-point links that omit it entirely give None.
+    pub fn get_effective_sci(&self, src_mac: MacAddress) -> Option<Sci> {
+        match self.sci {
+            Some(sci) => Some(sci),
+            None if self.is_end_station() => Some(Sci::new(src_mac, 1)),
+            None => None,
+        }
+    }
+}
+
+/// A MACsec protected frame payload split into SecTAG, secure data and ICV.
+#[derive(Clone, Copy, Debug)]
+pub struct MacsecView<'a> {
+    sectag: SecTag,
+    secure_data: &'a [u8],
+    icv: &'a [u8],
+}
+
+impl<'a> MacsecView<'a> {
+    /// Parses everything after the MACsec EtherType, assuming the default 16 byte ICV.
+    pub fn parse(bytes: &'a [u8]) -> Result<MacsecView<'a>, MacsecError> {
+        MacsecView::parse_with_icv_len(bytes, DEFAULT_ICV_LEN)
+    }
+
+    pub fn parse_with_icv_len(bytes: &'a [u8], icv_len: usize) -> Result<MacsecView<'a>, MacsecError> {
+        let sectag = SecTag::parse(bytes)?;
+        let start = sectag.byte_len();
+        if bytes.len() < start + icv_len {
+            return Err(MacsecError::Truncated { needed: start + icv_len, actual: bytes.len() });
+        }
+        // A non-zero short length means the frame was padded, and gives the real data length.
+        let data_len = if sectag.short_length != 0 {
+            if bytes.len() < start + sectag.short_length as usize + icv_len {
+                return Err(MacsecError::BadShortLength(sectag.short_length));
+            }
+            sectag.short_length as usize
+        }
+        else {
+            bytes.len() - start - icv_len
+        };
+        let end = start + data_len;
+        Ok(MacsecView { sectag, secure_data: &bytes[start..end], icv: &bytes[end..end + icv_len] })
+    }
+
+    pub fn from_frame(frame: &FrameView<'a>) -> Result<MacsecView<'a>, MacsecError> {
+        match frame.get_ethertype() {
+            Ok(NetworkProtocol::MAC_Sec) => MacsecView::parse(frame.get_network_payload()),
+            _ => Err(MacsecError::NotMacsec(frame.get_ethertype_raw())),
+        }
+    }
+
+    pub fn get_sectag(&self) -> SecTag {
+        self.sectag
+    }
+
+    /// The protected payload: ciphertext if the frame is encrypted, otherwise the original
+    /// EtherType and payload in the clear.
+    pub fn get_secure_data(&self) -> &'a [u8] {
+        self.secure_data
+    }
+
+    pub fn get_icv(&self) -> &'a [u8] {
+        self.icv
+    }
+}
+
+/// Tracks the highest packet number seen per secure association, to spot replayed frames.
+///
+/// A window of zero requires strictly increasing packet numbers, like a receiver with
+/// replay protection and no reordering allowance.
+#[derive(Clone, Debug, Default)]
+pub struct ReplayDetector {
+    window: u32,
+    highest: HashMap<([u8;8], u8), u32>,
+}
+
+impl ReplayDetector {
+    pub fn new(window: u32) -> ReplayDetector {
+        ReplayDetector { window, highest: HashMap::new() }
+    }
+
+    /// Records a packet number for the given SCI and AN. Returns true if it falls below the
+    /// replay window, i.e. a receiver enforcing replay protection would have dropped it.
+    pub fn check(&mut self, sci: Sci, an: u8, packet_number: u32) -> bool {
+        let key = (sci.to_bytes(), an);
+        match self.highest.get(&key).cloned() {
+            Some(highest) => {
+                let lowest_acceptable = highest.saturating_add(1).saturating_sub(self.window);
+                if packet_number > highest {
+                    self.highest.insert(key, packet_number);
+                }
+                packet_number < lowest_acceptable
+            },
+            None => {
+                self.highest.insert(key, packet_number);
+                false
+            },
+        }
+    }
+
+    /// Checks a parsed frame. Frames without an explicit or implied SCI are tracked by source MAC.
+    pub fn check_frame(&mut self, src_mac: MacAddress, sectag: &SecTag) -> bool {
+        let sci = sectag.get_effective_sci(src_mac).unwrap_or_else(|| Sci::new(src_mac, 0));
+        self.check(sci, sectag.get_an(), sectag.get_packet_number())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::ethernet::FrameBuilder;
+
+    fn macsec_payload(tci_an: u8, short_length: u8, pn: u32, sci: Option<[u8;8]>, data_len: usize) -> Vec<u8> {
+        let mut bytes = vec![tci_an, short_length];
+        bytes.extend_from_slice(&pn.to_be_bytes());
+        if let Some(sci) = sci {
+            bytes.extend_from_slice(&sci);
+        }
+        bytes.extend(vec![0xEE; data_len]);
+        bytes.extend(vec![0x1C; DEFAULT_ICV_LEN]);
+        bytes
+    }
+
+    #[test]
+    fn parses_encrypted_frame_with_sci() {
+        let payload = macsec_payload(0x2D, 0, 42, Some([0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x00, 0x01]), 64);
+        let mac = MacAddress::from_bytes([0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        let bytes = FrameBuilder::new(mac, mac, NetworkProtocol::MAC_Sec).payload(&payload).build();
+        let frame = FrameView::new(&bytes).unwrap();
+        let macsec = MacsecView::from_frame(&frame).unwrap();
+        let sectag = macsec.get_sectag();
+        assert!(sectag.is_encrypted() && sectag.is_changed_text());
+        assert_eq!(sectag.get_an(), 1);
+        assert_eq!(sectag.get_packet_number(), 42);
+        assert_eq!(sectag.get_sci(), Some(Sci::new(mac, 1)));
+        assert_eq!(macsec.get_secure_data().len(), 64);
+        assert_eq!(macsec.get_icv(), &[0x1C; 16]);
+    }
+
+    #[test]
+    fn short_length_excludes_padding() {
+        let mut payload = macsec_payload(0x40, 10, 7, None, 10);
+        payload.extend(vec![0; 12]);
+        let macsec = MacsecView::parse(&payload).unwrap();
+        assert!(!macsec.get_sectag().is_encrypted());
+        assert_eq!(macsec.get_secure_data(), &[0xEE; 10][..]);
+        assert_eq!(macsec.get_icv(), &[0x1C; DEFAULT_ICV_LEN][..]);
+
+        // The builder pads the frame to 60 bytes after the ICV.
+        let src = MacAddress::from_bytes([0x02, 0, 0, 0, 0, 9]);
+        let bytes = FrameBuilder::new(src, src, NetworkProtocol::MAC_Sec).payload(&macsec_payload(0x40, 10, 7, None, 10)).build();
+        let frame = FrameView::new(&bytes).unwrap();
+        assert!(frame.get_payload().len() > 6 + 10 + DEFAULT_ICV_LEN);
+        let macsec = MacsecView::from_frame(&frame).unwrap();
+        assert_eq!(macsec.get_secure_data(), &[0xEE; 10][..]);
+        assert_eq!(macsec.get_icv(), &[0x1C; DEFAULT_ICV_LEN][..]);
+
+        assert_eq!(macsec.get_sectag().get_effective_sci(src), Some(Sci::new(src, 1)));
+        assert_eq!(MacsecView::parse(&macsec_payload(0x40, 40, 7, None, 10)).unwrap_err(), MacsecError::BadShortLength(40));
+        assert_eq!(MacsecView::parse(&macsec_payload(0x80, 0, 7, None, 10)).unwrap_err(), MacsecError::UnsupportedVersion);
+    }
+
+    #[test]
+    fn detects_replayed_packet_numbers() {
+        let sci = Sci::new(MacAddress::from_bytes([0x02, 0, 0, 0, 0, 1]), 1);
+        let mut strict = ReplayDetector::new(0);
+        assert!(!strict.check(sci, 0, 10));
+        assert!(!strict.check(sci, 0, 11));
+        assert!(strict.check(sci, 0, 11));
+        assert!(!strict.check(sci, 1, 5));
+
+        let mut windowed = ReplayDetector::new(4);
+        assert!(!windowed.check(sci, 0, 100));
+        assert!(!windowed.check(sci, 0, 97));
+        assert!(windowed.check(sci, 0, 96));
+    }
+}
@@ -1 +1,7 @@
+pub mod aoe;
+pub mod arp;
 pub mod ethernet;
+pub mod fcoe;
+pub mod lldp;
+pub mod macsec;
+pub mod wol;
@@ -0,0 +1,148 @@
+use std::error::Error;
+use std::fmt;
+
+use layer3::NetworkProtocol;
+use super::ethernet::{FrameBuilder,FrameView,MacAddress};
+
+// Magic packets over UDP usually go to the discard port, sometimes to echo.
+pub const WOL_UDP_PORT: u16 = 9;
+pub const WOL_UDP_PORT_ALT: u16 = 7;
+
+const SYNC_LEN: usize = 6;
+const MAC_REPETITIONS: usize = 16;
+const MAGIC_PACKET_LEN: usize = SYNC_LEN + MAC_REPETITIONS * 6;
+
+#[derive(Debug)]
+pub struct SecureOnPasswordLengthError(pub usize);
+
+impl Error for SecureOnPasswordLengthError {
+    fn description(&self) -> &str {
+        "SecureOn password must be 4 or 6 bytes."
+    }
+}
+
+impl fmt::Display for SecureOnPasswordLengthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"SecureOn password must be 4 or 6 bytes. Length: {}", self.0)
+    }
+}
+
+/// A Wake-on-LAN magic packet: six 0xFF bytes, the target MAC sixteen times, and an optional
+/// SecureOn password. The same bytes work as a raw Ethernet payload or a UDP payload.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MagicPacket {
+    target: MacAddress,
+    password: Option<Vec<u8>>,
+}
+
+impl MagicPacket {
+    pub fn new(target: MacAddress) -> MagicPacket {
+        MagicPacket { target, password: None }
+    }
+
+    pub fn with_password(target: MacAddress, password: &[u8]) -> Result<MagicPacket, SecureOnPasswordLengthError> {
+        if password.len() != 4 && password.len() != 6 {
+            return Err(SecureOnPasswordLengthError(password.len()));
+        }
+        Ok(MagicPacket { target, password: Some(password.to_vec()) })
+    }
+
+    /// Looks for a magic packet anywhere in `bytes`, so a whole UDP datagram or frame payload
+    /// can be passed in. A password is only picked up if exactly 4 or 6 bytes follow the
+    /// packet, since anything else is more likely padding.
+    pub fn find(bytes: &[u8]) -> Option<MagicPacket> {
+        if bytes.len() < MAGIC_PACKET_LEN {
+            return None;
+        }
+        for start in 0..=bytes.len() - MAGIC_PACKET_LEN {
+            if bytes[start..start + SYNC_LEN].iter().any(|x| *x != 0xFF) {
+                continue;
+            }
+            let body = &bytes[start + SYNC_LEN..start + MAGIC_PACKET_LEN];
+            let mac = &body[..6];
+            if body.chunks(6).all(|x| x == mac) {
+                let rest = &bytes[start + MAGIC_PACKET_LEN..];
+                let password = if rest.len() == 4 || rest.len() == 6 { Some(rest.to_vec()) } else { None };
+                return Some(MagicPacket { target: MacAddress::from_slice(mac), password });
+            }
+        }
+        None
+    }
+
+    /// Checks the payload of any frame, whether it is raw EtherType 0x0842 or UDP inside IP.
+    pub fn from_frame(frame: &FrameView) -> Option<MagicPacket> {
+        MagicPacket::find(frame.get_network_payload())
+    }
+
+    pub fn get_target(&self) -> MacAddress {
+        self.target
+    }
+
+    pub fn get_password(&self) -> Option<&[u8]> {
+        self.password.as_deref()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0xFF; SYNC_LEN];
+        for _ in 0..MAC_REPETITIONS {
+            bytes.extend_from_slice(&self.target.to_bytes());
+        }
+        if let Some(ref password) = self.password {
+            bytes.extend_from_slice(password);
+        }
+        bytes
+    }
+
+    /// The raw Ethernet form, broadcast with EtherType 0x0842. For the UDP form, send
+    /// `to_bytes()` to WOL_UDP_PORT.
+    pub fn to_frame(&self, src_mac: MacAddress) -> FrameBuilder {
+        FrameBuilder::new(MacAddress::broadcast(), src_mac, NetworkProtocol::Wake_on_LAN).payload(&self.to_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::ethernet::{LlcHeader,SnapHeader,SAP_SNAP,OUI_RFC1042};
+
+    #[test]
+    fn builds_and_finds_raw_frames() {
+        let target = MacAddress::from_bytes([0x00, 0x1B, 0x21, 0x0A, 0x0B, 0x0C]);
+        let packet = MagicPacket::with_password(target, &[1, 2, 3, 4, 5, 6]).unwrap();
+        assert_eq!(packet.to_bytes().len(), 108);
+
+        let bytes = packet.to_frame(MacAddress::from_bytes([0x02, 0, 0, 0, 0, 1])).build();
+        let frame = FrameView::new(&bytes).unwrap();
+        assert_eq!(frame.get_ethertype().unwrap(), NetworkProtocol::Wake_on_LAN);
+        assert_eq!(MagicPacket::from_frame(&frame), Some(packet.clone()));
+
+        // The password is whatever follows the repetitions, so the LLC/SNAP bytes must not
+        // shift the packet.
+        let mut snap = LlcHeader::new(SAP_SNAP, SAP_SNAP, 0x03).to_bytes();
+        snap.extend_from_slice(&SnapHeader::new(OUI_RFC1042, 0x0842).to_bytes());
+        snap.extend_from_slice(&packet.to_bytes());
+        let bytes = FrameBuilder::ieee8023(MacAddress::broadcast(), MacAddress::from_bytes([0x02, 0, 0, 0, 0, 1])).payload(&snap).build();
+        let frame = FrameView::new(&bytes).unwrap();
+        assert_eq!(frame.get_ethertype().unwrap(), NetworkProtocol::Wake_on_LAN);
+        assert_eq!(MagicPacket::from_frame(&frame), Some(packet));
+    }
+
+    #[test]
+    fn finds_packet_inside_udp_payload() {
+        let target = MacAddress::from_bytes([0x00, 0x1B, 0x21, 0x0A, 0x0B, 0x0C]);
+        let mut datagram = vec![0xFF, 0x00, 0x09, 0x00, 0x09, 0x00, 0x72, 0x00, 0x00, 0xFF];
+        datagram.extend(MagicPacket::new(target).to_bytes());
+        let found = MagicPacket::find(&datagram).unwrap();
+        assert_eq!(found.get_target(), target);
+        assert!(found.get_password().is_none());
+    }
+
+    #[test]
+    fn rejects_near_misses() {
+        let mut bytes = MagicPacket::new(MacAddress::from_bytes([0x0A;6])).to_bytes();
+        bytes[50] = 0;
+        assert!(MagicPacket::find(&bytes).is_none());
+        assert!(MagicPacket::find(&bytes[..101]).is_none());
+        assert!(MagicPacket::with_password(MacAddress::from_bytes([0x0A;6]), &[1, 2, 3]).is_err());
+    }
+}
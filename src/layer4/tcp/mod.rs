@@ -20,7 +20,7 @@ pub struct TCPPort {
 
 impl TCPPort {
     pub fn new(protocol: TransportProtocol, port_num: u16) -> TCPPort {
-        TCPPort { proto: protocol, port_num: port_num }
+        TCPPort { proto: protocol, port_num }
     }
 
     pub fn get_proto(&self) -> TransportProtocol {
@@ -43,7 +43,7 @@ impl TCPPortRange {
         if first_port_num > second_port_num {
             panic!("Error in creating TCPPortRange: first port number must be smaller than the second.")
         }
-        TCPPortRange { proto: proto, port_num1: first_port_num, port_num2: second_port_num }
+        TCPPortRange { proto, port_num1: first_port_num, port_num2: second_port_num }
     }
 
     pub fn get_protocol(&self) -> TransportProtocol {
@@ -5,7 +5,7 @@
 #[macro_use] extern crate netdefs_macros;
 #[macro_use] extern crate procedural_masquerade;
 #[doc(hidden)] pub extern crate phf as _internal__phf;
-extern crate regex;
+extern crate chrono;
 
 pub use netdefs_macros::*;
 